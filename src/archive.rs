@@ -0,0 +1,67 @@
+use std::io::Read;
+
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::TestcontainerError;
+
+pub(crate) fn relative_path(path: &str) -> Result<&str, TestcontainerError> {
+    match path.strip_prefix('/') {
+        Some(relative) if !relative.is_empty() && !relative.ends_with('/') => Ok(relative),
+        _ => Err(TestcontainerError::Generic {
+            message: format!("'{path}' is not an absolute file path"),
+        }),
+    }
+}
+
+pub(crate) fn pack_file(
+    name: &str,
+    contents: &[u8],
+    mode: u32,
+) -> Result<Vec<u8>, TestcontainerError> {
+    let mut header = Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(mode);
+    header.set_entry_type(EntryType::Regular);
+
+    let mut builder = Builder::new(Vec::new());
+    builder.append_data(&mut header, name, contents)?;
+    Ok(builder.into_inner()?)
+}
+
+pub(crate) fn unpack_file(path: &str, archive: &[u8]) -> Result<Vec<u8>, TestcontainerError> {
+    let mut archive = Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous => {
+                let mut contents = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut contents)?;
+                return Ok(contents);
+            }
+            EntryType::Directory => {
+                return Err(TestcontainerError::Generic {
+                    message: format!("'{path}' is a directory"),
+                })
+            }
+            _ => continue,
+        }
+    }
+
+    Err(TestcontainerError::Generic {
+        message: format!("'{path}' is not a regular file"),
+    })
+}
+
+pub(crate) fn list_entries(archive: &[u8]) -> Result<Vec<String>, TestcontainerError> {
+    let mut archive = Archive::new(archive);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?;
+        let mut components = path.components().skip(1);
+        if let (Some(child), None) = (components.next(), components.next()) {
+            entries.push(child.as_os_str().to_string_lossy().into_owned());
+        }
+    }
+    Ok(entries)
+}
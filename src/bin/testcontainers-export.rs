@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use testcontainers_async::bollard::Docker;
+use testcontainers_async::modules::cockroachdb::CockroachDbImage;
+use testcontainers_async::modules::mysql::MySqlImage;
+use testcontainers_async::modules::postgresql::PostgresImage;
+use testcontainers_async::modules::redis::RedisImage;
+use testcontainers_async::{offline, Image};
+
+const USAGE: &str = "Usage: testcontainers-export [--dir <directory>] [--modules] [<image>...]
+
+Exports images with `docker save` semantics into <directory>, named so that
+tests running with TESTCONTAINERS_IMAGE_ARCHIVE_DIR=<directory> load them
+instead of pulling from a registry.
+
+Options:
+  --dir <directory>  Target directory (default: $TESTCONTAINERS_IMAGE_ARCHIVE_DIR or ./image-archives)
+  --modules          Include the default images of the built-in modules
+  --help             Print this message";
+
+fn main() -> ExitCode {
+    match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(run()),
+        Err(error) => {
+            eprintln!("Unable to start a runtime: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> ExitCode {
+    let mut directory = offline::archive_dir().unwrap_or_else(|| PathBuf::from("image-archives"));
+    let mut images = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => match args.next() {
+                Some(value) => directory = PathBuf::from(value),
+                None => {
+                    eprintln!("--dir requires a value\n\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--modules" => {
+                images.push(CockroachDbImage::default().settings().fullname());
+                images.push(MySqlImage::default().settings().fullname());
+                images.push(PostgresImage::default().settings().fullname());
+                images.push(RedisImage::default().settings().fullname());
+            }
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            image if !image.starts_with('-') => images.push(image.to_owned()),
+            unknown => {
+                eprintln!("Unknown option '{unknown}'\n\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if images.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let docker = match Docker::connect_with_local_defaults() {
+        Ok(docker) => docker,
+        Err(error) => {
+            eprintln!("Unable to connect to Docker: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for image in images {
+        match offline::export_image(&docker, &image, &directory).await {
+            Ok(path) => println!("{image} -> {}", path.display()),
+            Err(error) => {
+                eprint!("Failed to export {image}: {error}");
+                let mut source = std::error::Error::source(&error);
+                while let Some(error) = source {
+                    eprint!(": {error}");
+                    source = error.source();
+                }
+                eprintln!();
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
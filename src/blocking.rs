@@ -0,0 +1,123 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::executor;
+use crate::task::Task;
+use crate::{
+    AdminContainer, Container, ContainerRuntime, DatabaseContainer, Image, ServiceContainer,
+    TestcontainerError,
+};
+
+pub fn start_container<I: Image + Sync>(
+    image: I,
+) -> Result<BlockingContainer<I::ContainerType>, TestcontainerError> {
+    let container = block_on(image.start_container())?;
+    Ok(BlockingContainer { container })
+}
+
+pub fn start_container_with_runtime<I: Image + Sync>(
+    image: I,
+    runtime: Arc<dyn ContainerRuntime>,
+) -> Result<BlockingContainer<I::ContainerType>, TestcontainerError> {
+    let container = block_on(image.start_container_with_runtime(runtime))?;
+    Ok(BlockingContainer { container })
+}
+
+// Wraps a started container so that its operations can be called from synchronous code. The async
+// API remains available through `Deref`, for use inside `block_on`.
+pub struct BlockingContainer<C: Container> {
+    container: C,
+}
+
+impl<C: Container + Sync> BlockingContainer<C> {
+    pub fn into_inner(self) -> C {
+        self.container
+    }
+
+    pub fn host_port_for(&self, port: &str) -> Result<u16, TestcontainerError> {
+        block_on(self.container.host_port_for(port))
+    }
+
+    pub fn execute<T, R>(&self, task: T) -> Result<R, TestcontainerError>
+    where
+        T: Into<Box<dyn Task<Return = R> + 'static + Send + Sync>>,
+        T: Send,
+        R: 'static + Send + Sync,
+    {
+        block_on(self.container.execute(task))
+    }
+
+    pub fn stop(&self) -> Result<(), TestcontainerError> {
+        block_on(self.container.stop())
+    }
+
+    pub fn start(&self) -> Result<(), TestcontainerError> {
+        block_on(self.container.start())
+    }
+}
+
+impl<C: ServiceContainer + Sync> BlockingContainer<C> {
+    pub fn service_port(&self) -> Result<u16, TestcontainerError> {
+        block_on(self.container.service_port())
+    }
+}
+
+impl<C: AdminContainer + Sync> BlockingContainer<C> {
+    pub fn admin_port(&self) -> Result<u16, TestcontainerError> {
+        block_on(self.container.admin_port())
+    }
+}
+
+impl<C: DatabaseContainer + Sync> BlockingContainer<C> {
+    pub fn protocol(&self) -> Result<&str, TestcontainerError> {
+        block_on(self.container.protocol())
+    }
+
+    pub fn username(&self) -> Result<&str, TestcontainerError> {
+        block_on(self.container.username())
+    }
+
+    pub fn password(&self) -> Result<&str, TestcontainerError> {
+        block_on(self.container.password())
+    }
+
+    pub fn database(&self) -> Result<&str, TestcontainerError> {
+        block_on(self.container.database())
+    }
+
+    pub fn jdbc_url(&self) -> Result<String, TestcontainerError> {
+        block_on(self.container.jdbc_url())
+    }
+
+    pub fn connect_cli(&self) -> Result<String, TestcontainerError> {
+        block_on(self.container.connect_cli())
+    }
+
+    pub fn connect_url(&self) -> Result<String, TestcontainerError> {
+        block_on(self.container.connect_url())
+    }
+}
+
+impl<C: Container> Deref for BlockingContainer<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.container
+    }
+}
+
+impl<C: Container> Debug for BlockingContainer<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingContainer")
+            .field("container", &self.container)
+            .finish()
+    }
+}
+
+// Every blocking call is driven by the runtime that already drives the Docker client's
+// connections, so none is built per call. Must not be called from within an async context.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    executor::io_runtime().block_on(future)
+}
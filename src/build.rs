@@ -0,0 +1,602 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use futures::StreamExt;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use log::{debug, error, info};
+use sha2::{Digest, Sha256};
+use tar::{Builder, EntryType, Header};
+
+use crate::bollard::image::{BuildImageOptions, RemoveImageOptions};
+use crate::bollard::Docker;
+use crate::executor;
+use crate::platform::Platform;
+use crate::session;
+use crate::TestcontainerError;
+
+const DEFAULT_DOCKERFILE: &str = "Dockerfile";
+const DOCKERIGNORE: &str = ".dockerignore";
+const BUILD_HASH_LABEL: &str = "org.testcontainers.build-hash";
+
+static REMOVE_ON_EXIT: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Default)]
+pub struct BuildContext {
+    directory: Option<PathBuf>,
+    files: BTreeMap<String, ContextFile>,
+    dockerfile: Option<String>,
+    build_args: HashMap<String, String>,
+    target: Option<String>,
+    remove_on_exit: bool,
+}
+
+#[derive(Clone, Debug)]
+enum ContextFile {
+    Contents { contents: Vec<u8>, mode: u32 },
+    Symlink { target: PathBuf },
+}
+
+impl BuildContext {
+    pub fn new() -> BuildContext {
+        Default::default()
+    }
+
+    pub fn from_directory<P: Into<PathBuf>>(directory: P) -> BuildContext {
+        BuildContext {
+            directory: Some(directory.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_dockerfile<C: Into<Vec<u8>>>(contents: C) -> BuildContext {
+        BuildContext::new().with_file(DEFAULT_DOCKERFILE, contents)
+    }
+
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    pub fn with_file<P: Into<String>, C: Into<Vec<u8>>>(mut self, path: P, contents: C) -> Self {
+        self.files.insert(
+            clean_path(&path.into()),
+            ContextFile::Contents {
+                contents: contents.into(),
+                mode: 0o644,
+            },
+        );
+        self
+    }
+
+    pub fn dockerfile(&self) -> &str {
+        self.dockerfile.as_deref().unwrap_or(DEFAULT_DOCKERFILE)
+    }
+
+    pub fn with_dockerfile<P: Into<String>>(mut self, path: P) -> Self {
+        self.dockerfile = Some(clean_path(&path.into()));
+        self
+    }
+
+    pub fn build_args(&self) -> &HashMap<String, String> {
+        &self.build_args
+    }
+
+    pub fn with_build_arg<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.build_args.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    pub fn with_target<T: Into<String>>(mut self, target: T) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn remove_on_exit(&self) -> bool {
+        self.remove_on_exit
+    }
+
+    pub fn with_remove_on_exit(mut self, remove_on_exit: bool) -> Self {
+        self.remove_on_exit = remove_on_exit;
+        self
+    }
+
+    pub(crate) fn archive(&self) -> Result<Vec<u8>, TestcontainerError> {
+        // The ignore file is read first, so that ignored paths are skipped while walking the
+        // directory rather than read and thrown away
+        let ignore = match self.files.get(DOCKERIGNORE) {
+            Some(ContextFile::Contents { contents, .. }) => {
+                DockerIgnore::parse(&String::from_utf8_lossy(contents))?
+            }
+            Some(ContextFile::Symlink { .. }) => DockerIgnore::default(),
+            None => match &self.directory {
+                Some(directory) => match std::fs::read(directory.join(DOCKERIGNORE)) {
+                    Ok(contents) => DockerIgnore::parse(&String::from_utf8_lossy(&contents))?,
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                        DockerIgnore::default()
+                    }
+                    Err(error) => return Err(error.into()),
+                },
+                None => DockerIgnore::default(),
+            },
+        };
+
+        let mut files = BTreeMap::new();
+        if let Some(directory) = &self.directory {
+            let walk = Walk {
+                root: directory,
+                ignore: &ignore,
+                dockerfile: self.dockerfile(),
+            };
+            walk.collect(directory, &mut files)?;
+        }
+        files.extend(self.files.clone());
+
+        let mut builder = Builder::new(Vec::new());
+        for (path, file) in files {
+            if path != self.dockerfile() && path != DOCKERIGNORE && ignore.is_excluded(&path) {
+                continue;
+            }
+
+            let mut header = Header::new_gnu();
+            header.set_mtime(0);
+            match file {
+                ContextFile::Contents { contents, mode } => {
+                    let contents = match &self.target {
+                        Some(target) if path == self.dockerfile() => {
+                            truncate_to_stage(&contents, target)?
+                        }
+                        _ => contents,
+                    };
+                    header.set_entry_type(EntryType::Regular);
+                    header.set_mode(mode);
+                    header.set_size(contents.len() as u64);
+                    builder.append_data(&mut header, &path, contents.as_slice())?;
+                }
+                ContextFile::Symlink { target } => {
+                    header.set_entry_type(EntryType::Symlink);
+                    header.set_mode(0o777);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &path, target)?;
+                }
+            }
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    // Identifies the context by what it was configured with, without reading the directory
+    pub(crate) fn config_key(&self) -> String {
+        let mut hasher = Sha256::new();
+        if let Some(directory) = &self.directory {
+            hasher.update(format!("directory={}", directory.display()));
+        }
+        for (path, file) in &self.files {
+            match file {
+                ContextFile::Contents { contents, mode } => {
+                    hasher.update(format!("\0file={path}:{mode:o}:"));
+                    hasher.update(Sha256::digest(contents));
+                }
+                ContextFile::Symlink { target } => {
+                    hasher.update(format!("\0link={path}:{}", target.display()));
+                }
+            }
+        }
+        hasher.update(format!("\0dockerfile={}", self.dockerfile()));
+        let build_args: BTreeMap<_, _> = self.build_args.iter().collect();
+        for (key, value) in build_args {
+            hasher.update(format!("\0{key}={value}"));
+        }
+        if let Some(target) = &self.target {
+            hasher.update(format!("\0target={target}"));
+        }
+        hasher.update(format!("\0remove_on_exit={}", self.remove_on_exit));
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub(crate) fn content_hash(&self, archive: &[u8], platform: Option<&Platform>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(archive);
+        if let Some(platform) = platform {
+            hasher.update(format!("\0platform={platform}"));
+        }
+        hasher.update(self.dockerfile());
+        let build_args: BTreeMap<_, _> = self.build_args.iter().collect();
+        for (key, value) in build_args {
+            hasher.update(format!("\0{key}={value}"));
+        }
+        if let Some(target) = &self.target {
+            hasher.update(format!("\0target={target}"));
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+pub(crate) async fn build_image(
+    docker: &Docker,
+    fullname: &str,
+    context: &BuildContext,
+    platform: Option<&Platform>,
+) -> Result<(), TestcontainerError> {
+    let archive = context.archive()?;
+    let hash = context.content_hash(&archive, platform);
+    let platform = platform.map(ToString::to_string).unwrap_or_default();
+
+    let cached = match docker.inspect_image(fullname).await {
+        Ok(image) => image
+            .config
+            .and_then(|config| config.labels)
+            .and_then(|labels| labels.get(BUILD_HASH_LABEL).cloned())
+            .map(|existing| existing == hash)
+            .unwrap_or(false),
+        Err(_) => false,
+    };
+
+    if cached {
+        debug!("Using cached build of {} ({})", fullname, &hash[..12]);
+    } else {
+        info!("Building image {} ({})", fullname, &hash[..12]);
+
+        let mut labels = HashMap::new();
+        labels.insert(BUILD_HASH_LABEL, hash.as_str());
+
+        let options = BuildImageOptions {
+            dockerfile: context.dockerfile(),
+            t: fullname,
+            rm: true,
+            buildargs: context
+                .build_args()
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect(),
+            labels,
+            platform: platform.as_str(),
+            ..Default::default()
+        };
+
+        let mut output = docker.build_image(options, None, Some(archive.into()));
+        while let Some(info) = output.next().await {
+            let info = info?;
+            if let Some(stream) = info.stream {
+                for line in stream.lines().filter(|line| !line.trim().is_empty()) {
+                    debug!("[build {}] {}", fullname, line);
+                }
+            }
+            if let Some(message) = info.error {
+                return Err(TestcontainerError::Generic {
+                    message: format!("Failed to build image {fullname}: {message}"),
+                });
+            }
+        }
+
+        info!("Built image {}", fullname);
+    }
+
+    if context.remove_on_exit() {
+        remove_on_exit(docker, fullname);
+    }
+
+    Ok(())
+}
+
+fn remove_on_exit(docker: &Docker, fullname: &str) {
+    let mut registered = REMOVE_ON_EXIT.lock().unwrap();
+    if !registered
+        .get_or_insert_with(Default::default)
+        .insert(fullname.to_owned())
+    {
+        return;
+    }
+
+    let docker = docker.clone();
+    let fullname = fullname.to_owned();
+    session::on_end(move || {
+        executor::block_on_thread(async move {
+            info!("Removing image {}", fullname);
+            let result = executor::compat(docker.remove_image(
+                &fullname,
+                Some(RemoveImageOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+                None,
+            ))
+            .await;
+
+            if let Err(error) = result {
+                error!("Error removing image '{}': {error}", fullname);
+            }
+
+            // A later build of the same image is removed again at the end of its own session
+            if let Ok(mut registered) = REMOVE_ON_EXIT.lock() {
+                if let Some(registered) = registered.as_mut() {
+                    registered.remove(&fullname);
+                }
+            }
+        });
+    });
+}
+
+struct Walk<'a> {
+    root: &'a Path,
+    ignore: &'a DockerIgnore,
+    dockerfile: &'a str,
+}
+
+impl Walk<'_> {
+    fn collect(
+        &self,
+        directory: &Path,
+        files: &mut BTreeMap<String, ContextFile>,
+    ) -> Result<(), TestcontainerError> {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = std::fs::symlink_metadata(&path)?;
+            let relative = path
+                .strip_prefix(self.root)
+                .expect("Entries are read from beneath the context root")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            if metadata.is_dir() {
+                if !self.ignore.skips_directory(&relative) {
+                    self.collect(&path, files)?;
+                }
+            } else if relative != self.dockerfile
+                && relative != DOCKERIGNORE
+                && self.ignore.is_excluded(&relative)
+            {
+                continue;
+            } else {
+                files.insert(relative, read_entry(&path, &metadata)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_entry(
+    path: &Path,
+    metadata: &std::fs::Metadata,
+) -> Result<ContextFile, TestcontainerError> {
+    if metadata.file_type().is_symlink() {
+        Ok(ContextFile::Symlink {
+            target: std::fs::read_link(path)?,
+        })
+    } else {
+        Ok(ContextFile::Contents {
+            contents: std::fs::read(path)?,
+            mode: file_mode(metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(_: &std::fs::Metadata) -> u32 {
+    0o644
+}
+
+fn clean_path(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// The pinned bollard release has no `target` build option, so the Dockerfile is cut off before the
+// stage that follows the requested one, which then becomes the final stage of the build. Whole
+// instructions are read, so continuation lines and heredoc bodies are never taken for a `FROM`.
+fn truncate_to_stage(dockerfile: &[u8], target: &str) -> Result<Vec<u8>, TestcontainerError> {
+    let dockerfile = String::from_utf8_lossy(dockerfile);
+    let mut in_target = false;
+    for instruction in instructions(&dockerfile) {
+        let words: Vec<&str> = instruction.text.split_whitespace().collect();
+        if words.first().map(|word| word.eq_ignore_ascii_case("FROM")) != Some(true) {
+            continue;
+        }
+        if in_target {
+            return Ok(dockerfile[..instruction.start].as_bytes().to_vec());
+        }
+        in_target = words.len() >= 4
+            && words[words.len() - 2].eq_ignore_ascii_case("AS")
+            && words[words.len() - 1].eq_ignore_ascii_case(target);
+    }
+
+    if in_target {
+        Ok(dockerfile.as_bytes().to_vec())
+    } else {
+        Err(TestcontainerError::Generic {
+            message: format!("Build target stage '{target}' is not defined in the Dockerfile"),
+        })
+    }
+}
+
+struct Instruction {
+    // Byte offset of the instruction's first line
+    start: usize,
+    // The instruction with its continuations joined, excluding any heredoc bodies
+    text: String,
+}
+
+fn instructions(dockerfile: &str) -> Vec<Instruction> {
+    let escape = escape_directive(dockerfile);
+    let mut lines = dockerfile.split_inclusive('\n').peekable();
+    let mut offset = 0;
+    let mut instructions = Vec::new();
+
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut current = line.trim_end();
+        loop {
+            match current.strip_suffix(escape) {
+                Some(continued) => text.push_str(continued),
+                None => {
+                    text.push_str(current);
+                    break;
+                }
+            }
+            // Comments and empty lines within a continuation are skipped, as Docker does
+            let next = loop {
+                match lines.next() {
+                    Some(next) => {
+                        offset += next.len();
+                        let trimmed = next.trim();
+                        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                            break Some(next);
+                        }
+                    }
+                    None => break None,
+                }
+            };
+            match next {
+                Some(next) => {
+                    text.push(' ');
+                    current = next.trim_end();
+                }
+                None => break,
+            }
+        }
+
+        for (terminator, strip_tabs) in heredocs(&text) {
+            for line in lines.by_ref() {
+                offset += line.len();
+                let line = line.trim_end_matches(['\r', '\n']);
+                let line = if strip_tabs {
+                    line.trim_start_matches('\t')
+                } else {
+                    line
+                };
+                if line == terminator {
+                    break;
+                }
+            }
+        }
+
+        instructions.push(Instruction { start, text });
+    }
+    instructions
+}
+
+// Parser directives may only appear at the very top of the Dockerfile
+fn escape_directive(dockerfile: &str) -> char {
+    for line in dockerfile.lines() {
+        let directive = line
+            .trim()
+            .strip_prefix('#')
+            .and_then(|directive| directive.split_once('='));
+        match directive {
+            Some((key, value)) if key.trim().eq_ignore_ascii_case("escape") => {
+                return value.trim().chars().next().unwrap_or('\\');
+            }
+            Some((key, _)) if key.trim().eq_ignore_ascii_case("syntax") => continue,
+            _ => break,
+        }
+    }
+    '\\'
+}
+
+// Finds `<<EOF`, `<<-EOF` and quoted `<<"EOF"` markers, returning each terminator and whether
+// leading tabs are stripped from the body
+fn heredocs(instruction: &str) -> Vec<(String, bool)> {
+    let mut heredocs = Vec::new();
+    let mut rest = instruction;
+    while let Some(index) = rest.find("<<") {
+        rest = &rest[index + 2..];
+        let strip_tabs = rest.starts_with('-');
+        let marker = rest.strip_prefix('-').unwrap_or(rest);
+        let marker = marker.trim_start_matches(['"', '\'']);
+        let terminator: String = marker
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if !terminator.is_empty() {
+            heredocs.push((terminator, strip_tabs));
+        }
+    }
+    heredocs
+}
+
+#[derive(Default)]
+struct DockerIgnore {
+    patterns: Vec<(GlobMatcher, bool)>,
+    exceptions: Vec<String>,
+}
+
+impl DockerIgnore {
+    fn parse(contents: &str) -> Result<DockerIgnore, TestcontainerError> {
+        let mut patterns = Vec::new();
+        let mut exceptions = Vec::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (pattern, excluded) = match line.strip_prefix('!') {
+                Some(pattern) => (pattern.trim(), false),
+                None => (line, true),
+            };
+            let pattern = clean_path(pattern);
+            if pattern.is_empty() {
+                continue;
+            }
+            if !excluded {
+                exceptions.push(pattern.clone());
+            }
+            let matcher = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .build()
+                .or_else(|_| Glob::new(&globset::escape(&pattern)))
+                .map_err(|error| TestcontainerError::Generic {
+                    message: format!("Invalid {DOCKERIGNORE} pattern '{line}': {error}"),
+                })?
+                .compile_matcher();
+            patterns.push((matcher, excluded));
+        }
+        Ok(DockerIgnore {
+            patterns,
+            exceptions,
+        })
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        let mut excluded = false;
+        for (matcher, exclude) in &self.patterns {
+            let mut candidate = Some(path);
+            while let Some(current) = candidate {
+                if matcher.is_match(current) {
+                    excluded = *exclude;
+                    break;
+                }
+                candidate = current.rsplit_once('/').map(|(parent, _)| parent);
+            }
+        }
+        excluded
+    }
+
+    // An excluded directory is only walked when an exception may bring back something beneath it
+    fn skips_directory(&self, path: &str) -> bool {
+        self.is_excluded(path)
+            && !self.exceptions.iter().any(|exception| {
+                exception.starts_with(&format!("{path}/"))
+                    || exception.contains(['*', '?', '[', '{'])
+            })
+    }
+}
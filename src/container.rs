@@ -1,13 +1,26 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use async_trait::async_trait;
-use log::{error, info, warn};
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use log::{debug, error, info, warn};
 
-use crate::bollard::container::{InspectContainerOptions, RemoveContainerOptions};
+use crate::archive;
+use crate::bollard::errors::Error as BollardError;
+use crate::bollard::models::ContainerInspectResponse;
 use crate::bollard::Docker;
+use crate::errors;
 pub use crate::errors::TestcontainerError;
-use crate::{DropAction, ImageSettings, Qualifier, Task};
+use crate::events::{self, ContainerEvent};
+use crate::executor;
+use crate::info::ContainerInfo;
+use crate::runtime::ContainerRuntime;
+use crate::{DropAction, Image, ImageReference, ImageSettings, Qualifier, Task};
 
 const TESTCONTAINERS_DROP_ACTION: &str = "TESTCONTAINERS_DROP_ACTION";
 
@@ -27,40 +40,31 @@ pub trait Container: Sized + Debug {
     }
 
     async fn host_port_for(&self, port: &str) -> Result<u16, TestcontainerError> {
-        let result = self
-            .handle()
-            .docker
-            .inspect_container(&self.handle().id, None::<InspectContainerOptions>)
-            .await?;
-
-        if let Some(network_settings) = result.network_settings {
-            if let Some(port_map) = network_settings.ports {
-                for pair in port_map.iter() {
-                    if pair.0.starts_with(port) {
-                        if let Some(bindings) = pair.1 {
-                            if let Some(binding) = bindings.iter().next() {
-                                return Ok(binding
-                                    .host_port
-                                    .as_ref()
-                                    .map(|port| {
-                                        port.parse::<u16>()
-                                            .expect("Docker ports are expected to be u16")
-                                    })
-                                    .unwrap());
-                            }
-                        } else {
-                            return Err(TestcontainerError::UnexposedPort {
-                                portspec: port.to_owned(),
-                            });
-                        }
-                    }
-                }
+        let info = self.handle().state().await?;
+        if let Some(network_mode) = info.network_mode.filter(|mode| !mode.publishes_ports()) {
+            return Err(TestcontainerError::PortsNotPublished {
+                id: self.handle().id()[..12].to_owned(),
+                portspec: port.to_owned(),
+                network_mode: network_mode.to_string(),
+            });
+        }
+        match info
+            .ports
+            .iter()
+            .find(|(exposed, _)| exposed.starts_with(port))
+        {
+            Some((_, bindings)) => {
+                bindings
+                    .first()
+                    .copied()
+                    .ok_or_else(|| TestcontainerError::UnexposedPort {
+                        portspec: port.to_owned(),
+                    })
             }
+            None => Err(TestcontainerError::UndefinedPort {
+                portspec: port.to_owned(),
+            }),
         }
-
-        Err(TestcontainerError::UndefinedPort {
-            portspec: port.to_owned(),
-        })
     }
 
     async fn execute<T, R>(&self, task: T) -> Result<R, TestcontainerError>
@@ -73,21 +77,180 @@ pub trait Container: Sized + Debug {
         let result = task.execute(self.handle()).await?;
         Ok(result)
     }
+
+    async fn read_file_to_bytes(
+        &self,
+        container_path: &str,
+    ) -> Result<Vec<u8>, TestcontainerError> {
+        let archive = self.handle().download_archive(container_path).await?;
+        archive::unpack_file(container_path, &archive)
+    }
+
+    async fn copy_file_from<P>(
+        &self,
+        container_path: &str,
+        host_path: P,
+    ) -> Result<(), TestcontainerError>
+    where
+        P: AsRef<Path> + Send,
+    {
+        let contents = self.read_file_to_bytes(container_path).await?;
+        std::fs::write(host_path, contents)?;
+        Ok(())
+    }
+
+    async fn write_file<C>(
+        &self,
+        container_path: &str,
+        contents: C,
+    ) -> Result<(), TestcontainerError>
+    where
+        C: Into<Vec<u8>> + Send,
+    {
+        // Packing the full path and extracting at the root lets Docker create missing parents
+        let name = archive::relative_path(container_path)?;
+        let archive = archive::pack_file(name, &contents.into(), 0o644)?;
+
+        debug!(
+            "Writing {} to container {}",
+            container_path,
+            &self.handle().id[..12]
+        );
+
+        self.handle()
+            .runtime
+            .upload_archive(&self.handle().id, "/", archive)
+            .await
+    }
+
+    async fn list_dir(&self, container_path: &str) -> Result<Vec<String>, TestcontainerError> {
+        let archive = self.handle().download_archive(container_path).await?;
+        archive::list_entries(&archive)
+    }
+
+    async fn stop(&self) -> Result<(), TestcontainerError> {
+        info!("Stopping container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .stop_container(&self.handle().id, None)
+            .await
+    }
+
+    async fn stop_with_timeout(&self, timeout: Duration) -> Result<(), TestcontainerError> {
+        info!("Stopping container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .stop_container(&self.handle().id, Some(timeout))
+            .await
+    }
+
+    // Host ports are reassigned on every start, which `host_port_for` picks up by re-inspecting
+    async fn start(&self) -> Result<(), TestcontainerError> {
+        info!("Starting container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .start_container(&self.handle().id)
+            .await
+    }
+
+    async fn restart(&self) -> Result<(), TestcontainerError> {
+        info!("Restarting container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .restart_container(&self.handle().id)
+            .await
+    }
+
+    async fn pause(&self) -> Result<(), TestcontainerError> {
+        info!("Pausing container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .pause_container(&self.handle().id)
+            .await
+    }
+
+    async fn unpause(&self) -> Result<(), TestcontainerError> {
+        info!("Unpausing container {}", &self.handle().id[..12]);
+        self.handle()
+            .runtime
+            .unpause_container(&self.handle().id)
+            .await
+    }
+
+    async fn kill(&self) -> Result<(), TestcontainerError> {
+        self.kill_with_signal("SIGKILL").await
+    }
+
+    async fn kill_with_signal(&self, signal: &str) -> Result<(), TestcontainerError> {
+        info!(
+            "Sending {} to container {}",
+            signal,
+            &self.handle().id[..12]
+        );
+        self.handle()
+            .runtime
+            .kill_container(&self.handle().id, signal)
+            .await
+    }
+
+    async fn events(&self) -> BoxStream<'static, Result<ContainerEvent, TestcontainerError>> {
+        events::subscribe(
+            self.handle().runtime(),
+            &self.handle().id,
+            SystemTime::now(),
+        )
+    }
+
+    async fn info(&self) -> Result<ContainerInfo, TestcontainerError> {
+        self.handle().info().await
+    }
+
+    async fn is_running(&self) -> Result<bool, TestcontainerError> {
+        Ok(self.handle().state().await?.running)
+    }
+
+    async fn is_paused(&self) -> Result<bool, TestcontainerError> {
+        Ok(self.handle().state().await?.paused)
+    }
+
+    async fn exit_code(&self) -> Result<Option<i64>, TestcontainerError> {
+        Ok(self.handle().state().await?.exit_code)
+    }
+
+    async fn started_at(&self) -> Result<Option<DateTime<Utc>>, TestcontainerError> {
+        Ok(self.handle().state().await?.started_at)
+    }
+}
+
+// Links a container type back to the image it is started from, so that containers can be
+// provisioned by type alone; `#[derive(Image)]` implements it for non-generic images
+pub trait ContainerImage: Container {
+    type Image: Image<ContainerType = Self>;
 }
 
 #[derive(Debug)]
 pub struct ContainerHandle {
     id: String,
-    docker: Docker,
+    runtime: Arc<dyn ContainerRuntime>,
     drop_action: DropAction,
+    owned: bool,
+    reusable: bool,
+    cleaned_up: AtomicBool,
 }
 
 impl ContainerHandle {
     pub fn new(id: String, docker: Docker) -> ContainerHandle {
+        ContainerHandle::from_runtime(id, Arc::new(docker))
+    }
+
+    pub fn from_runtime(id: String, runtime: Arc<dyn ContainerRuntime>) -> ContainerHandle {
         ContainerHandle {
             id,
-            docker,
+            runtime,
             drop_action: Default::default(),
+            owned: true,
+            reusable: false,
+            cleaned_up: AtomicBool::new(false),
         }
     }
 
@@ -109,106 +272,183 @@ impl ContainerHandle {
         self
     }
 
+    pub fn runtime(&self) -> &dyn ContainerRuntime {
+        self.runtime.as_ref()
+    }
+
+    #[deprecated(note = "use runtime(), or try_docker() for the full bollard API")]
     pub fn docker(&self) -> &Docker {
-        &self.docker
+        self.try_docker()
+            .expect("The container is not backed by a Docker daemon; use runtime() instead")
     }
-}
 
-impl Drop for ContainerHandle {
-    fn drop(&mut self) {
-        let mut drop_action = self.drop_action.clone();
+    // Only handles backed by a Docker daemon can reach the full bollard API
+    pub fn try_docker(&self) -> Option<&Docker> {
+        self.runtime.docker()
+    }
+
+    // Handles to containers started elsewhere are not owned: they retain the container unless
+    // told otherwise, and TESTCONTAINERS_DROP_ACTION does not apply to them
+    pub fn is_owned(&self) -> bool {
+        self.owned
+    }
+
+    pub(crate) fn set_owned(&mut self, owned: bool) -> &mut Self {
+        self.owned = owned;
+        self
+    }
 
-        if let Ok(value) = std::env::var(TESTCONTAINERS_DROP_ACTION) {
+    pub fn is_reusable(&self) -> bool {
+        self.reusable
+    }
+
+    pub(crate) fn set_reusable(&mut self, reusable: bool) -> &mut Self {
+        self.reusable = reusable;
+        self
+    }
+
+    pub fn effective_drop_action(&self) -> DropAction {
+        self.effective_drop_action_with(std::env::var(TESTCONTAINERS_DROP_ACTION).ok().as_deref())
+    }
+
+    // Resolves the drop action against an override, as read from TESTCONTAINERS_DROP_ACTION
+    pub fn effective_drop_action_with(&self, override_action: Option<&str>) -> DropAction {
+        if !self.owned {
+            return self.drop_action.clone();
+        }
+        if let Some(value) = override_action {
             match value.to_lowercase().as_str() {
-                "remove" => drop_action = DropAction::Remove,
-                "retain" => drop_action = DropAction::Retain,
-                "stop" => drop_action = DropAction::Stop,
+                "remove" => return DropAction::Remove,
+                "retain" => return DropAction::Retain,
+                "stop" => return DropAction::Stop,
                 value => warn!(
                     "'{}' is not a valid value for {}",
                     value, TESTCONTAINERS_DROP_ACTION
                 ),
             }
         }
+        self.drop_action.clone()
+    }
 
-        match drop_action {
-            DropAction::Remove => {
-                let id = self.id.clone();
-                let docker = self.docker.clone();
-                let (sender, receiver) = std::sync::mpsc::channel();
-                std::thread::spawn(move || {
-                    let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async {
-                        info!("Removing container {}", &id[..12]);
-                        let result = docker
-                            .remove_container(
-                                id.as_str(),
-                                Some(RemoveContainerOptions {
-                                    force: true,
-                                    ..Default::default()
-                                }),
-                            )
-                            .await;
-
-                        match result {
-                            Ok(_) => {}
-                            Err(error) => {
-                                error!("Error removing container by id '{}': {error}", &id[..12]);
-                            }
-                        }
-
-                        let _ = sender.send(());
-                    });
-                });
-                let _ = receiver.recv();
-            }
-            DropAction::Retain => info!("Retaining container {}", &self.id[..12]),
-            DropAction::Stop => {
-                let id = self.id.clone();
-                let docker = self.docker.clone();
-                let (sender, receiver) = std::sync::mpsc::channel();
-                std::thread::spawn(move || {
-                    let rt = tokio::runtime::Runtime::new().unwrap();
-                    rt.block_on(async {
-                        info!("Stopping container {id}");
-                        let result = docker.stop_container(id.as_str(), None).await;
-
-                        match result {
-                            Ok(_) => {}
-                            Err(error) => {
-                                error!("Error stopping container by id '{}': {error}", &id[..12]);
-                            }
-                        }
-
-                        let _ = sender.send(());
-                    });
-                });
-                let _ = receiver.recv();
+    // Hands the container's cleanup over to a new handle, after which dropping this one leaves
+    // the container alone
+    pub(crate) fn take_cleanup(&mut self) -> ContainerHandle {
+        let cleaned_up = self.cleaned_up.swap(true, Ordering::SeqCst);
+        ContainerHandle {
+            id: self.id.clone(),
+            runtime: self.runtime.clone(),
+            drop_action: self.drop_action.clone(),
+            owned: self.owned,
+            reusable: self.reusable,
+            cleaned_up: AtomicBool::new(cleaned_up),
+        }
+    }
+
+    pub async fn cleanup(&self) -> Result<(), TestcontainerError> {
+        if self.reusable || self.cleaned_up.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        apply_drop_action(self.runtime(), &self.id, &self.effective_drop_action()).await
+    }
+
+    pub async fn info(&self) -> Result<ContainerInfo, TestcontainerError> {
+        let mut info = ContainerInfo::inspect(self.runtime(), &self.id).await?;
+        info.resolve_image_digest(self.runtime()).await;
+        Ok(info)
+    }
+
+    pub(crate) async fn state(&self) -> Result<ContainerInfo, TestcontainerError> {
+        ContainerInfo::inspect(self.runtime(), &self.id).await
+    }
+
+    async fn download_archive(&self, path: &str) -> Result<Vec<u8>, TestcontainerError> {
+        debug!("Reading {} from container {}", path, &self.id[..12]);
+        self.runtime.download_archive(&self.id, path).await
+    }
+}
+
+impl Drop for ContainerHandle {
+    fn drop(&mut self) {
+        if self.reusable {
+            info!("Keeping reusable container {}", &self.id[..12]);
+            return;
+        }
+        if self.cleaned_up.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let drop_action = self.effective_drop_action();
+        if let DropAction::Retain = drop_action {
+            info!("Retaining container {}", &self.id[..12]);
+            return;
+        }
+
+        let id = self.id.clone();
+        let runtime = self.runtime.clone();
+        executor::block_on_thread(async move {
+            let _ = apply_drop_action(runtime.as_ref(), &id, &drop_action).await;
+        });
+    }
+}
+
+async fn apply_drop_action(
+    runtime: &dyn ContainerRuntime,
+    id: &str,
+    drop_action: &DropAction,
+) -> Result<(), TestcontainerError> {
+    let result = match drop_action {
+        DropAction::Remove => {
+            info!("Removing container {}", &id[..12]);
+            runtime.remove_container(id).await
+        }
+        DropAction::Retain => {
+            info!("Retaining container {}", &id[..12]);
+            Ok(())
+        }
+        DropAction::Stop => {
+            info!("Stopping container {}", &id[..12]);
+            // Tests may already have stopped the container themselves
+            match runtime.stop_container(id, None).await {
+                Err(TestcontainerError::DockerError {
+                    source: BollardError::DockerResponseNotModifiedError { .. },
+                }) => Ok(()),
+                result => result,
             }
         }
+    };
+
+    if let Err(error) = &result {
+        error!(
+            "Error applying {:?} to container '{}': {}",
+            drop_action,
+            &id[..12],
+            errors::Chain(error)
+        );
     }
+    result
 }
 
 #[derive(Debug)]
 pub struct ContainerSettings {
-    name: String,
-    qualifier: Qualifier,
+    reference: ImageReference,
     env: HashMap<String, Option<String>>,
 }
 
 impl ContainerSettings {
     pub fn name(&self) -> &str {
-        &self.name
+        self.reference.name()
+    }
+
+    pub fn reference(&self) -> &ImageReference {
+        &self.reference
     }
 
     pub fn fullname(&self) -> String {
-        match &self.qualifier {
-            Qualifier::Tag(tag) => format!("{}:{}", self.name, tag),
-            Qualifier::Digest(digest) => format!("{}@{}", self.name, digest),
-        }
+        self.reference.to_string()
     }
 
     pub fn qualifier(&self) -> &Qualifier {
-        &self.qualifier
+        self.reference.qualifier()
     }
 
     pub fn environment(&self) -> &HashMap<String, Option<String>> {
@@ -216,11 +456,37 @@ impl ContainerSettings {
     }
 }
 
+impl ContainerSettings {
+    // Containers created from an image ID carry no name, so the image's own reference stands in
+    pub(crate) fn from_inspect(
+        inspect: &ContainerInspectResponse,
+        fallback: &ImageReference,
+    ) -> ContainerSettings {
+        let config = inspect.config.as_ref();
+        let reference = config
+            .and_then(|config| config.image.as_deref())
+            .filter(|image| !image.starts_with("sha256:"))
+            .and_then(|image| ImageReference::parse(image).ok())
+            .unwrap_or_else(|| fallback.clone());
+
+        let env = config
+            .and_then(|config| config.env.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|variable| match variable.split_once('=') {
+                Some((key, value)) => (key.to_owned(), Some(value.to_owned())),
+                None => (variable.to_owned(), None),
+            })
+            .collect();
+
+        ContainerSettings { reference, env }
+    }
+}
+
 impl From<&ImageSettings> for ContainerSettings {
     fn from(settings: &ImageSettings) -> Self {
         ContainerSettings {
-            name: settings.name().to_owned(),
-            qualifier: settings.qualifier().clone(),
+            reference: settings.reference().clone(),
             env: settings.environment().clone(),
         }
     }
@@ -1,3 +1,14 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use bollard::errors::Error as BollardError;
+
+use crate::runtime::ContainerRuntime;
+
+const DOCKER_HOST: &str = "DOCKER_HOST";
+const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
 #[derive(thiserror::Error, Debug)]
 pub enum TestcontainerError {
     #[error("Error: {message}")]
@@ -6,9 +17,142 @@ pub enum TestcontainerError {
     UnexposedPort { portspec: String },
     #[error("Request port {portspec} is not defined for this image.")]
     UndefinedPort { portspec: String },
-    #[error("Docker Error")]
+    #[error("Container {id} uses the {network_mode} network mode, which publishes no host port for {portspec}")]
+    PortsNotPublished {
+        id: String,
+        portspec: String,
+        network_mode: String,
+    },
+    #[error("{option} cannot be set for containers in the {network_mode} network mode")]
+    NetworkModeConflict {
+        option: String,
+        network_mode: String,
+    },
+    #[error("Invalid image reference '{reference}': {reason}")]
+    InvalidImageReference { reference: String, reason: String },
+    #[error("Image {image} is built for {actual}, but {expected} was requested")]
+    PlatformMismatch {
+        image: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Image {image} was not found; check the name and tag, and that you are logged in to its registry: {message}")]
+    ImageNotFound { image: String, message: String },
+    #[error("Pulling image {image} failed")]
+    PullFailed { image: String, source: BollardError },
+    #[error("Creating a container for {image} failed")]
+    CreateFailed { image: String, source: BollardError },
+    #[error("A container named '{name}' already exists: {message}")]
+    NameConflict { name: String, message: String },
+    #[error("Container {id} ({image}) could not bind a host port; is another container or process using it? {message}")]
+    PortConflict {
+        id: String,
+        image: String,
+        message: String,
+    },
+    #[error("Container {id} ({image}) was killed for running out of memory ({memory})")]
+    OutOfMemory {
+        id: String,
+        image: String,
+        memory: String,
+    },
+    #[error("Container {id} ({image}) was not ready after {timeout:?}")]
+    StartupTimeout {
+        id: String,
+        image: String,
+        timeout: Duration,
+    },
+    #[error("Container {id} ({image}) exited during startup{}; check its logs", describe_exit_code(*.exit_code))]
+    ContainerExited {
+        id: String,
+        image: String,
+        exit_code: Option<i64>,
+    },
+    #[error("Container {id} ({image}) failed during startup: {reason}")]
+    StartupFailed {
+        id: String,
+        image: String,
+        reason: String,
+    },
+    #[error("Command {command:?} in container {id} exited with {exit_code:?}: {output}")]
+    ExecFailed {
+        id: String,
+        command: Vec<String>,
+        exit_code: Option<i64>,
+        output: String,
+    },
+    #[error("Task {task} for container {id} ({image}) failed")]
+    TaskFailed {
+        id: String,
+        image: String,
+        task: String,
+        source: Box<TestcontainerError>,
+    },
+    #[error("Unable to reach the Docker daemon at {host}; is the Docker daemon running?")]
+    DaemonUnavailable { host: String, source: BollardError },
+    #[error("Docker error")]
     DockerError {
         #[from]
-        source: bollard::errors::Error,
+        source: BollardError,
+    },
+    #[error("I/O error")]
+    IoError {
+        #[from]
+        source: std::io::Error,
     },
 }
+
+fn describe_exit_code(exit_code: Option<i64>) -> String {
+    match exit_code {
+        Some(exit_code) => format!(" with code {exit_code}"),
+        None => String::new(),
+    }
+}
+
+pub(crate) fn docker_host() -> String {
+    std::env::var(DOCKER_HOST).unwrap_or_else(|_| DEFAULT_DOCKER_HOST.to_owned())
+}
+
+// Any request can be the first to reach a missing daemon, so failures are only attributed to the
+// daemon once it has been shown to be unreachable
+pub(crate) async fn explain_docker_error(
+    runtime: &dyn ContainerRuntime,
+    error: TestcontainerError,
+) -> TestcontainerError {
+    let docker_failure = matches!(
+        error,
+        TestcontainerError::DockerError { .. }
+            | TestcontainerError::PullFailed { .. }
+            | TestcontainerError::CreateFailed { .. }
+    );
+    if !docker_failure || runtime.ping().await.is_ok() {
+        return error;
+    }
+
+    match error {
+        TestcontainerError::DockerError { source }
+        | TestcontainerError::PullFailed { source, .. }
+        | TestcontainerError::CreateFailed { source, .. } => {
+            TestcontainerError::DaemonUnavailable {
+                host: docker_host(),
+                source,
+            }
+        }
+        error => error,
+    }
+}
+
+// Displays an error followed by its sources, for logs and panics that no chain reporter prints
+pub(crate) struct Chain<'a>(pub(crate) &'a dyn Error);
+
+impl Display for Chain<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(error) = source {
+            write!(f, ": {error}")?;
+            source = error.source();
+        }
+        Ok(())
+    }
+}
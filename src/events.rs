@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use log::debug;
+
+use crate::bollard::models::SystemEventsResponse;
+use crate::errors;
+use crate::runtime::ContainerRuntime;
+use crate::TestcontainerError;
+
+const HEALTH_STATUS_PREFIX: &str = "health_status";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerEvent {
+    action: String,
+    time: Option<i64>,
+    attributes: HashMap<String, String>,
+}
+
+impl ContainerEvent {
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn time(&self) -> Option<i64> {
+        self.time
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn is_die(&self) -> bool {
+        self.action == "die"
+    }
+
+    pub fn is_oom(&self) -> bool {
+        self.action == "oom"
+    }
+
+    // Health events carry the new status in the action, e.g. `health_status: unhealthy`
+    pub fn health_status(&self) -> Option<&str> {
+        self.action
+            .strip_prefix(HEALTH_STATUS_PREFIX)
+            .map(|status| status.trim_start_matches(':').trim())
+    }
+
+    pub fn exit_code(&self) -> Option<i64> {
+        self.attributes.get("exitCode")?.parse().ok()
+    }
+
+    pub(crate) fn failure(&self) -> Option<String> {
+        if self.is_die() {
+            Some(match self.exit_code() {
+                Some(exit_code) => format!("container exited with code {exit_code}"),
+                None => "container exited".to_owned(),
+            })
+        } else if self.is_oom() {
+            Some("container ran out of memory".to_owned())
+        } else if self.health_status() == Some("unhealthy") {
+            Some("container became unhealthy".to_owned())
+        } else {
+            None
+        }
+    }
+}
+
+impl From<SystemEventsResponse> for ContainerEvent {
+    fn from(event: SystemEventsResponse) -> Self {
+        ContainerEvent {
+            action: event.action.unwrap_or_default(),
+            time: event.time,
+            attributes: event
+                .actor
+                .and_then(|actor| actor.attributes)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// Subscriptions only reach the daemon once polled, so events are replayed from `since` rather
+// than trusting the subscription to be live before the requests that cause them
+pub(crate) fn subscribe(
+    runtime: &dyn ContainerRuntime,
+    id: &str,
+    since: SystemTime,
+) -> BoxStream<'static, Result<ContainerEvent, TestcontainerError>> {
+    runtime
+        .events(id, since)
+        .map(|event| Ok(ContainerEvent::from(event?)))
+        .boxed()
+}
+
+// Resolves with the event that stops the container from starting, and never resolves otherwise
+pub(crate) async fn watch_for_failure(
+    mut events: BoxStream<'static, Result<ContainerEvent, TestcontainerError>>,
+) -> ContainerEvent {
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) => {
+                debug!("Container event: {}", event.action());
+                if event.failure().is_some() {
+                    return event;
+                }
+            }
+            Err(err) => {
+                debug!("Container events unavailable: {}", errors::Chain(&err));
+                break;
+            }
+        }
+    }
+    futures::future::pending().await
+}
@@ -0,0 +1,201 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::Stream;
+use tokio::runtime::Runtime;
+
+#[cfg(any(
+    feature = "runtime-tokio",
+    feature = "runtime-async-std",
+    feature = "runtime-smol"
+))]
+use crate::TestcontainerError;
+
+#[cfg(not(any(
+    feature = "runtime-tokio",
+    feature = "runtime-async-std",
+    feature = "runtime-smol"
+)))]
+compile_error!(
+    "one of the runtime-tokio, runtime-async-std or runtime-smol features must be enabled"
+);
+
+// Features unify across a dependency graph, so more than one runtime may be enabled. The first of
+// tokio, async-std and smol that is enabled is the one used.
+
+static IO_RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+// bollard is built on hyper, which needs a tokio reactor. Docker calls are polled inside this
+// runtime's context whichever executor drives them, so its connections outlive any single test
+// and are never tied to a `current_thread` runtime that is blocked waiting on them.
+pub(crate) fn io_runtime() -> &'static Runtime {
+    IO_RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("testcontainers-io")
+            .enable_all()
+            .build()
+            .expect("Unable to start the testcontainers IO runtime")
+    })
+}
+
+pub(crate) fn compat<T>(inner: T) -> Compat<T> {
+    Compat {
+        inner: Box::pin(inner),
+    }
+}
+
+pub(crate) struct Compat<T> {
+    inner: Pin<Box<T>>,
+}
+
+impl<T: Future> Future for Compat<T> {
+    type Output = T::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _guard = io_runtime().enter();
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl<T: Stream> Stream for Compat<T> {
+    type Item = T::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let _guard = io_runtime().enter();
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    match future::select(Box::pin(future), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+// Drop handlers and exit hooks cannot await, and may run on a thread that is itself driving an
+// executor, so their cleanup is driven to completion on a thread of its own
+pub(crate) fn block_on_thread<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        block_on(future);
+        let _ = sender.send(());
+    });
+    let _ = receiver.recv();
+}
+
+#[cfg(feature = "runtime-tokio")]
+pub(crate) async fn sleep(duration: Duration) {
+    // Created inside the IO runtime's context, so the timer works under any executor
+    compat(async move { tokio::time::sleep(duration).await }).await
+}
+
+#[cfg(feature = "runtime-tokio")]
+pub(crate) async fn spawn<F>(future: F) -> Result<F::Output, TestcontainerError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    io_runtime()
+        .spawn(future)
+        .await
+        .map_err(|err| TestcontainerError::Generic {
+            message: format!("Spawned task failed: {err}"),
+        })
+}
+
+#[cfg(feature = "runtime-tokio")]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T, TestcontainerError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    io_runtime()
+        .spawn_blocking(f)
+        .await
+        .map_err(|err| TestcontainerError::Generic {
+            message: format!("Blocking task failed: {err}"),
+        })
+}
+
+#[cfg(feature = "runtime-tokio")]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    // The future runs on the calling thread, with the IO runtime's reactor and timers
+    io_runtime().block_on(future)
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) async fn sleep(duration: Duration) {
+    async_std::task::sleep(duration).await
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) async fn spawn<F>(future: F) -> Result<F::Output, TestcontainerError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Ok(async_std::task::spawn(future).await)
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T, TestcontainerError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Ok(async_std::task::spawn_blocking(f).await)
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    async_std::task::block_on(future)
+}
+
+#[cfg(all(
+    feature = "runtime-smol",
+    not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+))]
+pub(crate) async fn sleep(duration: Duration) {
+    smol::Timer::after(duration).await;
+}
+
+#[cfg(all(
+    feature = "runtime-smol",
+    not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+))]
+pub(crate) async fn spawn<F>(future: F) -> Result<F::Output, TestcontainerError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Ok(smol::spawn(future).await)
+}
+
+#[cfg(all(
+    feature = "runtime-smol",
+    not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+))]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T, TestcontainerError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Ok(smol::unblock(f).await)
+}
+
+#[cfg(all(
+    feature = "runtime-smol",
+    not(any(feature = "runtime-tokio", feature = "runtime-async-std"))
+))]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    smol::block_on(future)
+}
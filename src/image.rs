@@ -1,67 +1,176 @@
-use std::collections::HashMap;
+use std::any::type_name;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-use futures::TryStreamExt;
-use log::{debug, info};
+use futures::future::{self, Either};
+use futures::FutureExt;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
 
 use crate::bollard::container::Config;
-use crate::bollard::image::CreateImageOptions;
+use crate::bollard::errors::Error as BollardError;
 use crate::bollard::models::HostConfig;
 use crate::bollard::Docker;
+use crate::build::{self, BuildContext};
+use crate::errors;
+use crate::events::{self, ContainerEvent};
+use crate::executor;
+use crate::lockfile;
+use crate::offline;
+use crate::options::RuntimeOptions;
+use crate::platform::{self, Platform, PlatformCheck};
+use crate::reference::ImageReference;
+use crate::resources::{self, ResourceLimits};
+use crate::reuse;
+use crate::runtime::{self, ContainerRuntime};
+use crate::session;
+use crate::substitution;
 use crate::task::Task;
-use crate::{async_trait, Container, ContainerHandle, TestcontainerError};
+use crate::{async_trait, Container, ContainerHandle, ContainerSettings, TestcontainerError};
 
-#[derive(Clone, Debug)]
+pub type CreateCustomizer = Arc<dyn Fn(&mut Config<String>) + Send + Sync>;
+
+pub(crate) const LABEL_VERSION: &str = "org.testcontainers.version";
+pub(crate) const LABEL_IMAGE: &str = "org.testcontainers.image";
+pub(crate) const LABEL_SESSION: &str = "org.testcontainers.session-id";
+pub(crate) const LABEL_THREAD: &str = "org.testcontainers.thread";
+
+#[derive(Clone, Debug, Default)]
 pub enum DropAction {
+    #[default]
     Remove,
     Retain,
     Stop,
 }
 
-impl Default for DropAction {
-    fn default() -> Self {
-        DropAction::Remove
-    }
-}
-
 pub struct ImageSettings {
-    name: String,
-    qualifier: Qualifier,
+    reference: ImageReference,
     cmd: Option<Vec<String>>,
     entrypoint: Option<Vec<String>>,
     env: HashMap<String, Option<String>>,
     tasks: Vec<Box<dyn Task<Return = ()> + 'static + Send + Sync>>,
+    build_context: Option<BuildContext>,
+    archive: Option<PathBuf>,
+    platform: Option<Platform>,
+    platform_check: PlatformCheck,
+    reuse: bool,
+    reuse_enabled: bool,
+    container_name: Option<String>,
+    labels: BTreeMap<String, String>,
+    hostname: Option<String>,
+    domainname: Option<String>,
+    resources: ResourceLimits,
+    runtime_options: RuntimeOptions,
+    create_customizers: Vec<CreateCustomizer>,
+    startup_timeout: Option<Duration>,
 }
 
 impl ImageSettings {
     pub fn new<N: Into<String>, Q: Into<Qualifier>>(name: N, qualifier: Q) -> ImageSettings {
+        ImageSettings::from_reference(ImageReference::new(name, qualifier))
+    }
+
+    pub fn parse(reference: &str) -> Result<ImageSettings, TestcontainerError> {
+        Ok(ImageSettings::from_reference(ImageReference::parse(
+            reference,
+        )?))
+    }
+
+    pub fn from_reference(reference: ImageReference) -> ImageSettings {
         ImageSettings {
-            name: name.into(),
-            qualifier: qualifier.into(),
+            reference,
             cmd: Default::default(),
             entrypoint: Default::default(),
             env: Default::default(),
             tasks: Default::default(),
+            build_context: Default::default(),
+            archive: Default::default(),
+            platform: Default::default(),
+            platform_check: Default::default(),
+            reuse: Default::default(),
+            reuse_enabled: Default::default(),
+            container_name: Default::default(),
+            labels: Default::default(),
+            hostname: Default::default(),
+            domainname: Default::default(),
+            resources: Default::default(),
+            runtime_options: Default::default(),
+            create_customizers: Default::default(),
+            startup_timeout: Default::default(),
         }
     }
 
     pub fn name(&self) -> &str {
-        &self.name
+        self.reference.name()
+    }
+
+    pub fn reference(&self) -> &ImageReference {
+        &self.reference
     }
 
     pub fn fullname(&self) -> String {
-        match &self.qualifier {
-            Qualifier::Tag(tag) => format!("{}:{}", self.name, tag),
-            Qualifier::Digest(digest) => format!("{}@{}", self.name, digest),
+        self.reference.to_string()
+    }
+
+    pub fn resolved_fullname(&self) -> String {
+        // Locally built images are tagged with the name as given. A digest cannot be a tag, so
+        // only the tag is kept.
+        if self.build_context.is_some() {
+            self.reference.tagged()
+        } else {
+            substitution::substitute(&self.fullname())
+        }
+    }
+
+    pub fn resolved_reference(&self) -> Result<ImageReference, TestcontainerError> {
+        let reference = ImageReference::parse(&self.resolved_fullname())?;
+        if self.build_context.is_some() {
+            Ok(reference)
+        } else {
+            lockfile::pin(&self.reference, reference)
         }
     }
 
+    // Maps are sorted and build contexts hashed, so that equal settings always give the same key
+    pub(crate) fn config_key(&self) -> String {
+        let env: BTreeMap<_, _> = self.env.iter().collect();
+        let key = format!(
+            "{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+            self.fullname(),
+            self.cmd,
+            self.entrypoint,
+            env,
+            self.tasks,
+            self.build_context.as_ref().map(BuildContext::config_key),
+            self.archive,
+            self.platform,
+            self.reuse,
+            self.reuse_enabled,
+            self.container_name,
+            self.labels,
+            self.hostname,
+            self.domainname,
+            self.resources,
+            self.runtime_options,
+            self.startup_timeout,
+            // Closures cannot be compared, so customizers only match when they are the same instance
+            self.create_customizers
+                .iter()
+                .map(|customizer| Arc::as_ptr(customizer) as *const ())
+                .collect::<Vec<_>>()
+        );
+        format!("{:x}", Sha256::digest(key))
+    }
+
     pub fn qualifier(&self) -> &Qualifier {
-        &self.qualifier
+        self.reference.qualifier()
     }
 
     pub fn set_qualifier<Q: Into<Qualifier>>(&mut self, qualifier: Q) -> &mut Self {
-        self.qualifier = qualifier.into();
+        self.reference.set_qualifier(qualifier);
         self
     }
 
@@ -137,12 +246,237 @@ impl ImageSettings {
         self.append_task(task);
         self
     }
+
+    pub fn build_context(&self) -> Option<&BuildContext> {
+        self.build_context.as_ref()
+    }
+
+    pub fn set_build_context(&mut self, build_context: BuildContext) -> &mut ImageSettings {
+        self.build_context = Some(build_context);
+        self
+    }
+
+    pub fn with_build_context(mut self, build_context: BuildContext) -> ImageSettings {
+        self.set_build_context(build_context);
+        self
+    }
+
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    pub fn set_archive<P: Into<PathBuf>>(&mut self, archive: P) -> &mut ImageSettings {
+        self.archive = Some(archive.into());
+        self
+    }
+
+    pub fn with_archive<P: Into<PathBuf>>(mut self, archive: P) -> ImageSettings {
+        self.set_archive(archive);
+        self
+    }
+
+    pub fn platform(&self) -> Option<&Platform> {
+        self.platform.as_ref()
+    }
+
+    pub fn set_platform<P: Into<Platform>>(&mut self, platform: P) -> &mut ImageSettings {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    pub fn with_platform<P: Into<Platform>>(mut self, platform: P) -> ImageSettings {
+        self.set_platform(platform);
+        self
+    }
+
+    pub fn with_platform_str(self, platform: &str) -> Result<ImageSettings, TestcontainerError> {
+        Ok(self.with_platform(Platform::parse(platform)?))
+    }
+
+    pub fn platform_check(&self) -> PlatformCheck {
+        self.platform_check
+    }
+
+    pub fn set_platform_check(&mut self, platform_check: PlatformCheck) -> &mut ImageSettings {
+        self.platform_check = platform_check;
+        self
+    }
+
+    pub fn with_platform_check(mut self, platform_check: PlatformCheck) -> ImageSettings {
+        self.set_platform_check(platform_check);
+        self
+    }
+
+    pub fn reuse(&self) -> bool {
+        self.reuse
+    }
+
+    pub fn set_reuse(&mut self, reuse: bool) -> &mut ImageSettings {
+        self.reuse = reuse;
+        self
+    }
+
+    pub fn with_reuse(mut self, reuse: bool) -> ImageSettings {
+        self.set_reuse(reuse);
+        self
+    }
+
+    // Lets tests enable reuse without setting TESTCONTAINERS_REUSE_ENABLE for the whole process;
+    // everyone else opts in through the environment
+    #[cfg(feature = "test-support")]
+    #[doc(hidden)]
+    pub fn set_reuse_enabled(&mut self, reuse_enabled: bool) -> &mut ImageSettings {
+        self.reuse_enabled = reuse_enabled;
+        self
+    }
+
+    #[cfg(feature = "test-support")]
+    #[doc(hidden)]
+    pub fn with_reuse_enabled(mut self, reuse_enabled: bool) -> ImageSettings {
+        self.set_reuse_enabled(reuse_enabled);
+        self
+    }
+
+    pub(crate) fn is_reusable(&self) -> bool {
+        self.reuse && (self.reuse_enabled || reuse::is_enabled())
+    }
+
+    pub fn container_name(&self) -> Option<&str> {
+        self.container_name.as_deref()
+    }
+
+    pub fn set_container_name<N: Into<String>>(&mut self, name: N) -> &mut ImageSettings {
+        self.container_name = Some(name.into());
+        self
+    }
+
+    pub fn with_container_name<N: Into<String>>(mut self, name: N) -> ImageSettings {
+        self.set_container_name(name);
+        self
+    }
+
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+
+    pub fn set_label<K: Into<String>, V: Into<String>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> &mut ImageSettings {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_labels<I, K, V>(mut self, labels: I) -> ImageSettings
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (key, value) in labels {
+            self.set_label(key, value);
+        }
+        self
+    }
+
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    pub fn set_hostname<H: Into<String>>(&mut self, hostname: H) -> &mut ImageSettings {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    pub fn with_hostname<H: Into<String>>(mut self, hostname: H) -> ImageSettings {
+        self.set_hostname(hostname);
+        self
+    }
+
+    pub fn domainname(&self) -> Option<&str> {
+        self.domainname.as_deref()
+    }
+
+    pub fn set_domainname<D: Into<String>>(&mut self, domainname: D) -> &mut ImageSettings {
+        self.domainname = Some(domainname.into());
+        self
+    }
+
+    pub fn with_domainname<D: Into<String>>(mut self, domainname: D) -> ImageSettings {
+        self.set_domainname(domainname);
+        self
+    }
+
+    pub fn resources(&self) -> &ResourceLimits {
+        &self.resources
+    }
+
+    pub fn set_resources(&mut self, resources: ResourceLimits) -> &mut ImageSettings {
+        self.resources = resources;
+        self
+    }
+
+    pub fn with_resources(mut self, resources: ResourceLimits) -> ImageSettings {
+        self.set_resources(resources);
+        self
+    }
+
+    pub fn runtime_options(&self) -> &RuntimeOptions {
+        &self.runtime_options
+    }
+
+    pub fn set_runtime_options(&mut self, runtime_options: RuntimeOptions) -> &mut ImageSettings {
+        self.runtime_options = runtime_options;
+        self
+    }
+
+    pub fn with_runtime_options(mut self, runtime_options: RuntimeOptions) -> ImageSettings {
+        self.set_runtime_options(runtime_options);
+        self
+    }
+
+    pub fn startup_timeout(&self) -> Option<Duration> {
+        self.startup_timeout
+    }
+
+    pub fn set_startup_timeout(&mut self, startup_timeout: Duration) -> &mut ImageSettings {
+        self.startup_timeout = Some(startup_timeout);
+        self
+    }
+
+    pub fn with_startup_timeout(mut self, startup_timeout: Duration) -> ImageSettings {
+        self.set_startup_timeout(startup_timeout);
+        self
+    }
+
+    pub fn create_customizers(&self) -> &[CreateCustomizer] {
+        &self.create_customizers
+    }
+
+    pub fn append_create_customizer<F>(&mut self, customizer: F) -> &mut ImageSettings
+    where
+        F: Fn(&mut Config<String>) + Send + Sync + 'static,
+    {
+        self.create_customizers.push(Arc::new(customizer));
+        self
+    }
+
+    pub fn with_create_customizer<F>(mut self, customizer: F) -> ImageSettings
+    where
+        F: Fn(&mut Config<String>) + Send + Sync + 'static,
+    {
+        self.append_create_customizer(customizer);
+        self
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum Qualifier {
     Tag(String),
     Digest(String),
+    TagAndDigest(String, String),
 }
 
 impl Display for Qualifier {
@@ -150,6 +484,7 @@ impl Display for Qualifier {
         match self {
             Qualifier::Tag(value) => write!(f, "{}", value),
             Qualifier::Digest(value) => write!(f, "{}", value),
+            Qualifier::TagAndDigest(tag, digest) => write!(f, "{}@{}", tag, digest),
         }
     }
 }
@@ -162,14 +497,42 @@ impl Qualifier {
     pub fn digest<D: Into<String>>(digest: D) -> Qualifier {
         Qualifier::Digest(digest.into())
     }
+
+    pub fn tag_and_digest<T: Into<String>, D: Into<String>>(tag: T, digest: D) -> Qualifier {
+        Qualifier::TagAndDigest(tag.into(), digest.into())
+    }
+
+    pub fn tag_value(&self) -> Option<&str> {
+        match self {
+            Qualifier::Tag(tag) | Qualifier::TagAndDigest(tag, _) => Some(tag),
+            Qualifier::Digest(_) => None,
+        }
+    }
+
+    pub fn digest_value(&self) -> Option<&str> {
+        match self {
+            Qualifier::Digest(digest) | Qualifier::TagAndDigest(_, digest) => Some(digest),
+            Qualifier::Tag(_) => None,
+        }
+    }
+
+    pub(crate) fn suffix(&self) -> String {
+        match self {
+            Qualifier::Tag(tag) => format!(":{tag}"),
+            Qualifier::Digest(digest) => format!("@{digest}"),
+            Qualifier::TagAndDigest(tag, digest) => format!(":{tag}@{digest}"),
+        }
+    }
 }
 
 impl From<&str> for Qualifier {
     fn from(value: &str) -> Self {
-        if value.starts_with("sha256") {
-            Qualifier::digest(value)
-        } else {
-            Qualifier::tag(value)
+        // Tags may not contain ':' or '@', while digests always take the form 'algorithm:hex'.
+        // Values starting with "sha256" have always been taken for digests, so still are.
+        match value.split_once('@') {
+            Some((tag, digest)) => Qualifier::tag_and_digest(tag, digest),
+            None if value.contains(':') || value.starts_with("sha256") => Qualifier::digest(value),
+            None => Qualifier::tag(value),
         }
     }
 }
@@ -237,7 +600,105 @@ pub trait Image: Sized {
         self
     }
 
-    async fn on_before_start_container(&self, _: &Docker) -> Result<(), TestcontainerError> {
+    fn with_build_context(mut self, build_context: BuildContext) -> Self {
+        self.settings_mut().set_build_context(build_context);
+        self
+    }
+
+    fn with_archive<P: Into<PathBuf>>(mut self, archive: P) -> Self {
+        self.settings_mut().set_archive(archive);
+        self
+    }
+
+    fn with_platform<P: Into<Platform>>(mut self, platform: P) -> Self {
+        self.settings_mut().set_platform(platform);
+        self
+    }
+
+    fn with_platform_str(self, platform: &str) -> Result<Self, TestcontainerError> {
+        Ok(self.with_platform(Platform::parse(platform)?))
+    }
+
+    fn with_platform_check(mut self, platform_check: PlatformCheck) -> Self {
+        self.settings_mut().set_platform_check(platform_check);
+        self
+    }
+
+    fn with_reuse(mut self, reuse: bool) -> Self {
+        self.settings_mut().set_reuse(reuse);
+        self
+    }
+
+    #[cfg(feature = "test-support")]
+    #[doc(hidden)]
+    fn with_reuse_enabled(mut self, reuse_enabled: bool) -> Self {
+        self.settings_mut().set_reuse_enabled(reuse_enabled);
+        self
+    }
+
+    fn with_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.settings_mut().set_container_name(name);
+        self
+    }
+
+    fn with_labels<I, K, V>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (key, value) in labels {
+            self.settings_mut().set_label(key, value);
+        }
+        self
+    }
+
+    fn with_hostname<H: Into<String>>(mut self, hostname: H) -> Self {
+        self.settings_mut().set_hostname(hostname);
+        self
+    }
+
+    fn with_domainname<D: Into<String>>(mut self, domainname: D) -> Self {
+        self.settings_mut().set_domainname(domainname);
+        self
+    }
+
+    fn with_resources(mut self, resources: ResourceLimits) -> Self {
+        self.settings_mut().set_resources(resources);
+        self
+    }
+
+    fn with_runtime_options(mut self, runtime_options: RuntimeOptions) -> Self {
+        self.settings_mut().set_runtime_options(runtime_options);
+        self
+    }
+
+    fn with_startup_timeout(mut self, startup_timeout: Duration) -> Self {
+        self.settings_mut().set_startup_timeout(startup_timeout);
+        self
+    }
+
+    fn with_create_customizer<F>(mut self, customizer: F) -> Self
+    where
+        F: Fn(&mut Config<String>) + Send + Sync + 'static,
+    {
+        self.settings_mut().append_create_customizer(customizer);
+        self
+    }
+
+    fn configure(&self, _: &mut Config<String>) -> Result<(), TestcontainerError> {
+        Ok(())
+    }
+
+    #[deprecated(note = "override on_before_start_container_with_runtime instead")]
+    async fn on_before_start_container(&self, docker: &Docker) -> Result<(), TestcontainerError> {
+        self.on_before_start_container_with_runtime(docker).await
+    }
+
+    async fn on_before_start_container_with_runtime(
+        &self,
+        _: &dyn ContainerRuntime,
+    ) -> Result<(), TestcontainerError> {
         Ok(())
     }
 
@@ -248,44 +709,117 @@ pub trait Image: Sized {
         Ok(())
     }
 
+    #[deprecated(note = "override on_pull_image_with_runtime instead")]
     async fn on_pull_image(&self, docker: &Docker) -> Result<(), TestcontainerError> {
-        let inspect_result = docker
-            .inspect_image(self.settings().fullname().as_str())
+        self.on_pull_image_with_runtime(docker).await
+    }
+
+    async fn on_pull_image_with_runtime(
+        &self,
+        runtime: &dyn ContainerRuntime,
+    ) -> Result<(), TestcontainerError> {
+        let platform = self.settings().platform();
+
+        if let Some(build_context) = self.settings().build_context() {
+            let fullname = self.settings().resolved_fullname();
+            let docker = runtime::require_docker(runtime, "Building images")?;
+            executor::compat(build::build_image(
+                docker,
+                &fullname,
+                build_context,
+                platform,
+            ))
+            .await?;
+            return platform::verify(
+                runtime,
+                &fullname,
+                platform,
+                self.settings().platform_check(),
+            )
             .await;
+        }
+
+        let reference = self.settings().resolved_reference()?;
+        let fullname = reference.to_string();
+        let present = match runtime.inspect_image(&fullname).await {
+            Ok(image) => match platform {
+                Some(platform) if !platform.matches(&Platform::of_image(&image)) => {
+                    info!(
+                        "Cached image {} is built for {}, pulling {}",
+                        fullname,
+                        Platform::of_image(&image),
+                        platform
+                    );
+                    false
+                }
+                _ => true,
+            },
+            Err(_) => false,
+        };
 
         // TODO: Implement PullPolicy?
-        match inspect_result {
-            Ok(_) => (),
-            Err(_) => {
-                info!("Pulling image {}", self.settings().fullname());
-                docker
-                    .create_image(
-                        Some(CreateImageOptions {
-                            from_image: self.settings().fullname(),
-                            ..Default::default()
-                        }),
-                        None,
-                        None,
-                    )
-                    .try_collect::<Vec<_>>()
-                    .await?;
+        let refresh = lockfile::needs_refresh(&reference)?;
+        let archive = offline::find_archive(self.settings()).filter(|_| !refresh);
+        match (present && !refresh, archive) {
+            (true, _) => (),
+            (false, Some(archive)) => {
+                let docker = runtime::require_docker(runtime, "Loading image archives")?;
+                offline::load_archive(docker, &archive).await?;
+                if runtime.inspect_image(&fullname).await.is_err() {
+                    return Err(TestcontainerError::Generic {
+                        message: format!(
+                            "Image archive {} does not contain {}",
+                            archive.display(),
+                            reference.canonical()
+                        ),
+                    });
+                }
+            }
+            (false, None) => {
+                info!("Pulling image {}", reference.canonical());
+                let mut options = reference.pull_options();
+                if let Some(platform) = platform {
+                    options.platform = platform.to_string();
+                }
+                match runtime.pull_image(options).await {
+                    Ok(_) => (),
+                    Err(TestcontainerError::DockerError {
+                        source: BollardError::DockerResponseNotFoundError { message },
+                    }) => {
+                        return Err(TestcontainerError::ImageNotFound {
+                            image: reference.canonical(),
+                            message,
+                        })
+                    }
+                    Err(TestcontainerError::DockerError { source }) => {
+                        return Err(TestcontainerError::PullFailed {
+                            image: reference.canonical(),
+                            source,
+                        })
+                    }
+                    Err(err) => return Err(err),
+                }
             }
         }
 
-        Ok(())
+        platform::verify(
+            runtime,
+            &fullname,
+            platform,
+            self.settings().platform_check(),
+        )
+        .await?;
+        lockfile::record(runtime, self.settings().reference(), &reference).await
     }
 
-    async fn on_create_container(
+    async fn container_config_with_runtime(
         &self,
-        docker: Docker,
-    ) -> Result<ContainerHandle, TestcontainerError> {
-        let host_config = Some(HostConfig {
-            publish_all_ports: Some(true),
-
-            ..Default::default()
-        });
+        runtime: &dyn ContainerRuntime,
+    ) -> Result<Config<String>, TestcontainerError> {
+        let mut host_config = HostConfig::default();
+        self.settings().resources().apply(&mut host_config);
 
-        let env: Vec<String> = self
+        let mut env: Vec<String> = self
             .settings()
             .environment()
             .iter()
@@ -297,24 +831,81 @@ pub trait Image: Sized {
                 }
             })
             .collect();
+        env.sort();
+
+        let mut image = self.settings().resolved_reference()?.to_string();
+        if self.settings().platform().is_some() {
+            // The pinned bollard release cannot pass a platform when creating containers, so the
+            // container is created from the exact image that was pulled and verified for it
+            image = runtime.inspect_image(&image).await?.id;
+        }
 
-        let image_config = Config {
-            image: Some(self.settings().fullname()),
-            host_config,
+        let mut image_config = Config {
+            image: Some(image),
+            host_config: Some(host_config),
             cmd: self.settings().cmd().cloned(),
             entrypoint: self.settings().entrypoint().cloned(),
             env: Some(env),
-            tty: Some(true),
+            hostname: self.settings().hostname().map(ToOwned::to_owned),
+            domainname: self.settings().domainname().map(ToOwned::to_owned),
+            labels: Some(self.container_labels()),
             ..Default::default()
         };
+        self.settings().runtime_options().apply(&mut image_config)?;
+        for customizer in self.settings().create_customizers() {
+            customizer(&mut image_config);
+        }
+        self.configure(&mut image_config)?;
 
-        debug!("Creating container for {}", self.settings().fullname());
+        if self.settings().is_reusable() {
+            let hash = reuse::config_hash(&image_config)?;
+            image_config
+                .labels
+                .get_or_insert_with(Default::default)
+                .insert(reuse::REUSE_HASH_LABEL.to_owned(), hash);
+        }
 
-        let id = docker
-            .create_container::<&str, String>(None, image_config)
-            .await?
-            .id;
-        Ok(ContainerHandle::new(id, docker))
+        Ok(image_config)
+    }
+
+    fn container_labels(&self) -> HashMap<String, String> {
+        let mut labels: HashMap<String, String> = self
+            .settings()
+            .labels()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        labels.insert(
+            LABEL_VERSION.to_owned(),
+            env!("CARGO_PKG_VERSION").to_owned(),
+        );
+        labels.insert(LABEL_IMAGE.to_owned(), self.settings().fullname());
+        labels.insert(LABEL_SESSION.to_owned(), session::id().to_owned());
+        if let Some(thread) = std::thread::current().name() {
+            labels.insert(LABEL_THREAD.to_owned(), thread.to_owned());
+        }
+        labels
+    }
+
+    #[deprecated(note = "override on_create_container_with_runtime instead")]
+    async fn on_create_container(
+        &self,
+        docker: Docker,
+    ) -> Result<ContainerHandle, TestcontainerError> {
+        self.on_create_container_with_runtime(Arc::new(docker))
+            .await
+    }
+
+    async fn on_create_container_with_runtime(
+        &self,
+        runtime: Arc<dyn ContainerRuntime>,
+    ) -> Result<ContainerHandle, TestcontainerError> {
+        let image_config = self.container_config_with_runtime(runtime.as_ref()).await?;
+        create_from_config(self.settings(), runtime, image_config).await
+    }
+
+    async fn on_reuse_container(&self, handle: &ContainerHandle) -> Result<(), TestcontainerError> {
+        reuse::ensure_running(handle).await
     }
 
     async fn on_start_container(&self, handle: &ContainerHandle) -> Result<(), TestcontainerError> {
@@ -324,10 +915,21 @@ pub trait Image: Sized {
             self.settings().fullname()
         );
 
-        handle
-            .docker()
-            .start_container::<String>(handle.id(), None)
-            .await?;
+        match handle.runtime().start_container(handle.id()).await {
+            Ok(_) => (),
+            Err(TestcontainerError::DockerError {
+                source: BollardError::DockerResponseServerError { message, .. },
+            }) if message.contains("port is already allocated")
+                || message.contains("address already in use") =>
+            {
+                return Err(TestcontainerError::PortConflict {
+                    id: handle.id()[..12].to_owned(),
+                    image: self.settings().fullname(),
+                    message,
+                })
+            }
+            Err(err) => return Err(err),
+        }
 
         info!(
             "Started: {} ({})",
@@ -345,7 +947,14 @@ pub trait Image: Sized {
             self.settings().fullname()
         );
         for task in self.settings().tasks() {
-            task.execute(handle).await?;
+            task.execute(handle)
+                .await
+                .map_err(|err| TestcontainerError::TaskFailed {
+                    id: handle.id()[..12].to_owned(),
+                    image: self.settings().fullname(),
+                    task: format!("{task:?}"),
+                    source: Box::new(err),
+                })?;
         }
         info!(
             "Ready: {} ({})",
@@ -355,21 +964,256 @@ pub trait Image: Sized {
         Ok(())
     }
 
+    async fn attach_existing(
+        &self,
+        id_or_name: &str,
+        verify: bool,
+    ) -> Result<Self::ContainerType, TestcontainerError> {
+        let docker = Docker::connect_with_local_defaults()?;
+        self.attach_existing_with_docker(docker, id_or_name, verify)
+            .await
+    }
+
+    async fn attach_existing_with_docker(
+        &self,
+        docker: Docker,
+        id_or_name: &str,
+        verify: bool,
+    ) -> Result<Self::ContainerType, TestcontainerError> {
+        self.attach_existing_with_runtime(Arc::new(docker), id_or_name, verify)
+            .await
+    }
+
+    async fn attach_existing_with_runtime(
+        &self,
+        runtime: Arc<dyn ContainerRuntime>,
+        id_or_name: &str,
+        verify: bool,
+    ) -> Result<Self::ContainerType, TestcontainerError> {
+        let inspect = runtime.inspect_container(id_or_name).await?;
+        let id = inspect
+            .id
+            .clone()
+            .ok_or_else(|| TestcontainerError::Generic {
+                message: format!("Container {id_or_name} has no id"),
+            })?;
+        let settings = ContainerSettings::from_inspect(&inspect, self.settings().reference());
+
+        // The container is owned by whoever started it, so it is left alone when dropped
+        let mut handle =
+            ContainerHandle::from_runtime(id, runtime).with_drop_action(DropAction::Retain);
+        handle.set_owned(false);
+        info!("Attached: {} ({})", &handle.id()[..12], settings.fullname());
+
+        if verify {
+            reuse::ensure_running(&handle).await?;
+            self.on_execute_tasks(&handle).await?;
+        }
+        Ok(Self::ContainerType::attach(handle, settings))
+    }
+
     async fn start_container(&self) -> Result<Self::ContainerType, TestcontainerError> {
         let docker = Docker::connect_with_local_defaults()?;
-        self.start_container_with_docker(docker).await
+        let error = match self.start_container_with_docker(docker.clone()).await {
+            Ok(container) => return Ok(container),
+            Err(err) => err,
+        };
+        Err(errors::explain_docker_error(&docker, error).await)
     }
 
     async fn start_container_with_docker(
         &self,
         docker: Docker,
     ) -> Result<Self::ContainerType, TestcontainerError> {
-        self.on_pull_image(&docker).await?;
-        self.on_before_start_container(&docker).await?;
-        let handle = self.on_create_container(docker).await?;
-        self.on_start_container(&handle).await?;
-        self.on_after_start_container(&handle).await?;
-        self.on_execute_tasks(&handle).await?;
+        self.start_container_with_runtime(Arc::new(docker)).await
+    }
+
+    async fn start_container_with_runtime(
+        &self,
+        runtime: Arc<dyn ContainerRuntime>,
+    ) -> Result<Self::ContainerType, TestcontainerError> {
+        pull_image(self, runtime.as_ref()).await?;
+
+        let reusable = self.settings().is_reusable();
+        if self.settings().reuse() && !reusable {
+            warn!(
+                "Reuse requested for {}, but {} is not enabled",
+                self.settings().fullname(),
+                reuse::TESTCONTAINERS_REUSE_ENABLE
+            );
+        }
+        // A reusable container is created from the same configuration its hash was taken from
+        let reuse_config = if reusable {
+            let config = self.container_config_with_runtime(runtime.as_ref()).await?;
+            let hash = config
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.get(reuse::REUSE_HASH_LABEL))
+                .cloned()
+                .unwrap_or_default();
+            if let Some(id) = reuse::find_running(runtime.as_ref(), &hash).await? {
+                let mut handle = ContainerHandle::from_runtime(id, runtime);
+                handle.set_reusable(true);
+                self.on_reuse_container(&handle).await?;
+                return Ok(Self::ContainerType::attach(handle, self.settings().into()));
+            }
+            Some(config)
+        } else {
+            None
+        };
+
+        before_start_container(self, runtime.as_ref()).await?;
+        let since = SystemTime::now();
+        let mut handle = match reuse_config {
+            Some(config) => create_from_config(self.settings(), runtime, config).await?,
+            None => create_container(self, runtime).await?,
+        };
+        handle.set_reusable(reusable);
+        let started = async {
+            self.on_start_container(&handle).await?;
+            self.on_after_start_container(&handle).await?;
+            self.on_execute_tasks(&handle).await
+        };
+        let started = async {
+            match self.settings().startup_timeout() {
+                Some(timeout) => executor::timeout(timeout, started)
+                    .await
+                    .unwrap_or_else(|| {
+                        Err(TestcontainerError::StartupTimeout {
+                            id: handle.id()[..12].to_owned(),
+                            image: self.settings().fullname(),
+                            timeout,
+                        })
+                    }),
+                None => started.await,
+            }
+        };
+
+        // Containers without readiness tasks may legitimately exit straight away, so only
+        // startups that wait on the container are watched
+        let result = if self.settings().tasks().is_empty() {
+            started.await
+        } else {
+            let events = events::subscribe(handle.runtime(), handle.id(), since);
+            let failure = events::watch_for_failure(events);
+
+            let failed = |event: ContainerEvent| {
+                if event.is_die() {
+                    TestcontainerError::ContainerExited {
+                        id: handle.id()[..12].to_owned(),
+                        image: self.settings().fullname(),
+                        exit_code: event.exit_code(),
+                    }
+                } else {
+                    TestcontainerError::StartupFailed {
+                        id: handle.id()[..12].to_owned(),
+                        image: self.settings().fullname(),
+                        reason: event.failure().unwrap_or_default(),
+                    }
+                }
+            };
+
+            match future::select(Box::pin(started), Box::pin(failure)).await {
+                Either::Left((Ok(()), _)) => Ok(()),
+                // A task usually fails because the container went away, and an event that has
+                // already arrived says why more precisely than the task can
+                Either::Left((Err(err), failure)) => match failure.now_or_never() {
+                    Some(event) => Err(failed(event)),
+                    None => Err(err),
+                },
+                Either::Right((event, _)) => Err(failed(event)),
+            }
+        };
+        if let Err(err) = result {
+            let fullname = self.settings().fullname();
+            return Err(resources::explain_startup_failure(&handle, &fullname, err).await);
+        }
         Ok(Self::ContainerType::attach(handle, self.settings().into()))
     }
 }
+
+// Images written against the Docker-only hooks keep working: whenever a Docker daemon backs the
+// runtime the deprecated hooks are called, and by default they hand over to the runtime hooks.
+// Other runtimes cannot call them, which is reported once for every image type.
+fn warn_docker_hooks_skipped<I: Image>() {
+    static WARNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    if WARNED.lock().unwrap().insert(type_name::<I>()) {
+        warn!(
+            "{} is not started on Docker, so any override of on_pull_image, \
+             on_before_start_container or on_create_container is not called; override the \
+             *_with_runtime hooks instead",
+            type_name::<I>()
+        );
+    }
+}
+
+#[allow(deprecated)]
+async fn pull_image<I: Image + Sync>(
+    image: &I,
+    runtime: &dyn ContainerRuntime,
+) -> Result<(), TestcontainerError> {
+    match runtime.docker() {
+        Some(docker) => image.on_pull_image(docker).await,
+        None => {
+            warn_docker_hooks_skipped::<I>();
+            image.on_pull_image_with_runtime(runtime).await
+        }
+    }
+}
+
+#[allow(deprecated)]
+async fn before_start_container<I: Image + Sync>(
+    image: &I,
+    runtime: &dyn ContainerRuntime,
+) -> Result<(), TestcontainerError> {
+    match runtime.docker() {
+        Some(docker) => image.on_before_start_container(docker).await,
+        None => {
+            warn_docker_hooks_skipped::<I>();
+            image.on_before_start_container_with_runtime(runtime).await
+        }
+    }
+}
+
+async fn create_from_config(
+    settings: &ImageSettings,
+    runtime: Arc<dyn ContainerRuntime>,
+    config: Config<String>,
+) -> Result<ContainerHandle, TestcontainerError> {
+    debug!("Creating container for {}", settings.fullname());
+
+    let name = settings.container_name();
+    let id = match runtime.create_container(name, config).await {
+        Ok(id) => id,
+        Err(TestcontainerError::DockerError {
+            source: BollardError::DockerResponseConflictError { message },
+        }) if name.is_some() => {
+            return Err(TestcontainerError::NameConflict {
+                name: name.unwrap_or_default().to_owned(),
+                message,
+            })
+        }
+        Err(TestcontainerError::DockerError { source }) => {
+            return Err(TestcontainerError::CreateFailed {
+                image: settings.fullname(),
+                source,
+            })
+        }
+        Err(err) => return Err(err),
+    };
+    Ok(ContainerHandle::from_runtime(id, runtime))
+}
+
+#[allow(deprecated)]
+async fn create_container<I: Image + Sync>(
+    image: &I,
+    runtime: Arc<dyn ContainerRuntime>,
+) -> Result<ContainerHandle, TestcontainerError> {
+    match runtime.docker() {
+        Some(docker) => image.on_create_container(docker.clone()).await,
+        None => {
+            warn_docker_hooks_skipped::<I>();
+            image.on_create_container_with_runtime(runtime).await
+        }
+    }
+}
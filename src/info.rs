@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+
+use crate::bollard::models::{
+    ContainerInspectResponse, ContainerStateStatusEnum, HealthStatusEnum, MountPoint,
+};
+use crate::options::NetworkMode;
+use crate::runtime::ContainerRuntime;
+use crate::TestcontainerError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerStatus {
+    Created,
+    Running,
+    Paused,
+    Restarting,
+    Removing,
+    Exited,
+    Dead,
+    Unknown,
+}
+
+impl From<Option<ContainerStateStatusEnum>> for ContainerStatus {
+    fn from(status: Option<ContainerStateStatusEnum>) -> Self {
+        match status {
+            Some(ContainerStateStatusEnum::CREATED) => ContainerStatus::Created,
+            Some(ContainerStateStatusEnum::RUNNING) => ContainerStatus::Running,
+            Some(ContainerStateStatusEnum::PAUSED) => ContainerStatus::Paused,
+            Some(ContainerStateStatusEnum::RESTARTING) => ContainerStatus::Restarting,
+            Some(ContainerStateStatusEnum::REMOVING) => ContainerStatus::Removing,
+            Some(ContainerStateStatusEnum::EXITED) => ContainerStatus::Exited,
+            Some(ContainerStateStatusEnum::DEAD) => ContainerStatus::Dead,
+            _ => ContainerStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthStatus {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkInfo {
+    pub ip_address: Option<String>,
+    pub gateway: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MountInfo {
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub destination: Option<String>,
+    pub read_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub image_id: Option<String>,
+    pub image_digest: Option<String>,
+    pub status: ContainerStatus,
+    pub running: bool,
+    pub paused: bool,
+    pub health: Option<HealthStatus>,
+    pub exit_code: Option<i64>,
+    pub oom_killed: bool,
+    pub error: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub gateway: Option<String>,
+    pub network_mode: Option<NetworkMode>,
+    pub networks: BTreeMap<String, NetworkInfo>,
+    pub ports: BTreeMap<String, Vec<u16>>,
+    pub mounts: Vec<MountInfo>,
+    pub labels: HashMap<String, String>,
+}
+
+impl ContainerInfo {
+    pub(crate) async fn inspect(
+        runtime: &dyn ContainerRuntime,
+        id: &str,
+    ) -> Result<ContainerInfo, TestcontainerError> {
+        let inspect = runtime.inspect_container(id).await?;
+        Ok(ContainerInfo::from(inspect))
+    }
+
+    // Repository digests live on the image rather than the container, so resolving them costs
+    // a second request that state and port lookups can do without
+    pub(crate) async fn resolve_image_digest(&mut self, runtime: &dyn ContainerRuntime) {
+        if let Some(image_id) = &self.image_id {
+            if let Ok(image) = runtime.inspect_image(image_id).await {
+                self.image_digest = image
+                    .repo_digests
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find_map(|repo_digest| Some(repo_digest.split_once('@')?.1.to_owned()));
+            }
+        }
+    }
+
+    pub fn ip_address(&self) -> Option<&str> {
+        self.networks
+            .values()
+            .find_map(|network| network.ip_address.as_deref())
+    }
+
+    pub fn host_port(&self, port: &str) -> Option<u16> {
+        self.ports
+            .iter()
+            .find(|(exposed, _)| exposed.starts_with(port))
+            .and_then(|(_, bindings)| bindings.first().copied())
+    }
+}
+
+impl From<ContainerInspectResponse> for ContainerInfo {
+    fn from(inspect: ContainerInspectResponse) -> Self {
+        let state = inspect.state.unwrap_or_default();
+        let running = state.running.unwrap_or(false);
+        let network_settings = inspect.network_settings.unwrap_or_default();
+        let config = inspect.config.unwrap_or_default();
+
+        ContainerInfo {
+            id: inspect.id.unwrap_or_default(),
+            name: inspect
+                .name
+                .map(|name| name.trim_start_matches('/').to_owned()),
+            image: config.image,
+            image_id: inspect.image,
+            image_digest: None,
+            status: state.status.into(),
+            running,
+            paused: state.paused.unwrap_or(false),
+            health: state
+                .health
+                .and_then(|health| health.status)
+                .and_then(|status| match status {
+                    HealthStatusEnum::STARTING => Some(HealthStatus::Starting),
+                    HealthStatusEnum::HEALTHY => Some(HealthStatus::Healthy),
+                    HealthStatusEnum::UNHEALTHY => Some(HealthStatus::Unhealthy),
+                    _ => None,
+                }),
+            // Docker reports 0 for containers that are still running
+            exit_code: state.exit_code.filter(|_| !running),
+            oom_killed: state.oom_killed.unwrap_or(false),
+            error: state.error.filter(|error| !error.is_empty()),
+            created: inspect.created.as_deref().and_then(parse_time),
+            started_at: state.started_at.as_deref().and_then(parse_time),
+            finished_at: state.finished_at.as_deref().and_then(parse_time),
+            gateway: network_settings
+                .gateway
+                .filter(|gateway| !gateway.is_empty()),
+            network_mode: inspect
+                .host_config
+                .and_then(|host_config| host_config.network_mode)
+                .map(NetworkMode::from),
+            networks: network_settings
+                .networks
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, endpoint)| {
+                    let network = NetworkInfo {
+                        ip_address: endpoint.ip_address.filter(|ip| !ip.is_empty()),
+                        gateway: endpoint.gateway.filter(|gateway| !gateway.is_empty()),
+                        aliases: endpoint.aliases.unwrap_or_default(),
+                    };
+                    (name, network)
+                })
+                .collect(),
+            ports: network_settings
+                .ports
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(port, bindings)| {
+                    let host_ports = bindings
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|binding| binding.host_port?.parse().ok())
+                        .collect();
+                    (port, host_ports)
+                })
+                .collect(),
+            mounts: inspect
+                .mounts
+                .unwrap_or_default()
+                .into_iter()
+                .map(MountInfo::from)
+                .collect(),
+            labels: config.labels.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<MountPoint> for MountInfo {
+    fn from(mount: MountPoint) -> Self {
+        MountInfo {
+            kind: mount.typ,
+            name: mount.name,
+            source: mount.source,
+            destination: mount.destination,
+            read_only: !mount.rw.unwrap_or(true),
+        }
+    }
+}
+
+// The daemon reports RFC 3339 times, and the zero time for events that have not happened yet
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    if time.starts_with("0001-") {
+        return None;
+    }
+    match DateTime::parse_from_rfc3339(time) {
+        Ok(time) => Some(time.with_timezone(&Utc)),
+        Err(err) => {
+            warn!("Unable to parse container time '{time}': {err}");
+            None
+        }
+    }
+}
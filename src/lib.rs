@@ -1,17 +1,58 @@
 pub use async_trait::async_trait;
 pub use bollard;
+pub use testcontainers_async_derive::{test, Container, Image};
 
+pub use crate::build::BuildContext;
 pub use crate::container::{
-    AdminContainer, Container, ContainerHandle, ContainerSettings, DatabaseContainer,
-    ServiceContainer,
+    AdminContainer, Container, ContainerHandle, ContainerImage, ContainerSettings,
+    DatabaseContainer, ServiceContainer,
 };
 pub use crate::errors::TestcontainerError;
-pub use crate::image::{DropAction, Image, ImageSettings, Qualifier};
+pub use crate::events::ContainerEvent;
+pub use crate::image::{CreateCustomizer, DropAction, Image, ImageSettings, Qualifier};
+pub use crate::info::{ContainerInfo, ContainerStatus, HealthStatus, MountInfo, NetworkInfo};
+pub use crate::options::{NetworkMode, RuntimeOptions};
+pub use crate::platform::{Platform, PlatformCheck};
+pub use crate::reference::ImageReference;
+pub use crate::resources::{ResourceLimits, Ulimit};
+pub use crate::runtime::ContainerRuntime;
+pub use crate::scope::{with_container, with_containers, ContainerDiagnostics, ImageSet};
+pub use crate::shared::SharedContainer;
+pub use crate::substitution::ImageNameSubstitutor;
 pub use crate::task::Task;
 
+// Used by the code `#[testcontainers_async::test]` generates
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::executor::block_on;
+    pub use crate::scope::{run_test, TestOutcome};
+}
+
+// Lets derived impls name this crate the same way from inside it as from outside
+extern crate self as testcontainers_async;
+
+mod archive;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod build;
 mod container;
 mod errors;
+mod events;
+mod executor;
 mod image;
+mod info;
+pub mod lockfile;
 pub mod modules;
+pub mod offline;
+mod options;
+mod platform;
+mod reference;
+mod resources;
+pub mod reuse;
+pub mod runtime;
+mod scope;
+pub mod session;
+mod shared;
+pub mod substitution;
 mod task;
 pub mod tasks;
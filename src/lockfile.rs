@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{debug, info, warn};
+
+use crate::reference::is_valid_digest;
+use crate::runtime::ContainerRuntime;
+use crate::{ImageReference, Qualifier, TestcontainerError};
+
+pub const TESTCONTAINERS_LOCKFILE: &str = "TESTCONTAINERS_LOCKFILE";
+pub const TESTCONTAINERS_LOCKFILE_MODE: &str = "TESTCONTAINERS_LOCKFILE_MODE";
+pub const DEFAULT_LOCKFILE: &str = "testcontainers.lock";
+
+const HEADER: &str = "# Image digests pinned by testcontainers-async. Regenerate with \
+TESTCONTAINERS_LOCKFILE_MODE=record.";
+
+static ACTIVE: Mutex<Option<Option<Lockfile>>> = Mutex::new(None);
+static REFRESHED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockfileMode {
+    Use,
+    Record,
+    Strict,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lockfile {
+    path: PathBuf,
+    mode: LockfileMode,
+    digests: BTreeMap<String, String>,
+    changed: BTreeSet<String>,
+}
+
+impl Lockfile {
+    pub fn new<P: Into<PathBuf>>(path: P, mode: LockfileMode) -> Lockfile {
+        Lockfile {
+            path: path.into(),
+            mode,
+            digests: Default::default(),
+            changed: Default::default(),
+        }
+    }
+
+    pub fn load<P: Into<PathBuf>>(
+        path: P,
+        mode: LockfileMode,
+    ) -> Result<Lockfile, TestcontainerError> {
+        let mut lockfile = Lockfile::new(path, mode);
+        match std::fs::read_to_string(&lockfile.path) {
+            Ok(contents) => lockfile.digests = parse(&lockfile.path, &contents)?,
+            Err(err)
+                if err.kind() == std::io::ErrorKind::NotFound && mode == LockfileMode::Record => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(lockfile)
+    }
+
+    // Other processes may have recorded digests since this lockfile was loaded, so the file is
+    // read again and only the entries set here are written over it, all under an exclusive lock
+    pub fn save(&self) -> Result<(), TestcontainerError> {
+        let _lock = self.lock()?;
+        let mut digests = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => parse(&self.path, &contents)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => self.digests.clone(),
+            Err(err) => return Err(err.into()),
+        };
+        for reference in &self.changed {
+            if let Some(digest) = self.digests.get(reference) {
+                digests.insert(reference.clone(), digest.clone());
+            }
+        }
+
+        let mut contents = format!("{HEADER}\n");
+        for (reference, digest) in &digests {
+            contents.push_str(&format!("{reference} = {digest}\n"));
+        }
+        // Written aside and renamed into place, so that readers never see a partial lockfile
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(format!(".{}.tmp", std::process::id()));
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    // The lockfile itself is replaced on every save, so writers lock a file beside it instead
+    fn lock(&self) -> Result<File, TestcontainerError> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn mode(&self) -> LockfileMode {
+        self.mode
+    }
+
+    pub fn digest(&self, reference: &str) -> Option<&str> {
+        self.digests.get(reference).map(String::as_str)
+    }
+
+    pub fn set_digest<R: Into<String>, D: Into<String>>(
+        &mut self,
+        reference: R,
+        digest: D,
+    ) -> &mut Self {
+        let reference = reference.into();
+        self.digests.insert(reference.clone(), digest.into());
+        self.changed.insert(reference);
+        self
+    }
+
+    pub fn digests(&self) -> &BTreeMap<String, String> {
+        &self.digests
+    }
+
+    pub fn pin(&self, reference: ImageReference) -> Result<ImageReference, TestcontainerError> {
+        let key = reference.clone();
+        self.pin_as(&key, reference)
+    }
+
+    // Entries are keyed by the reference as written, so that a lockfile holds across environments
+    // that substitute image names differently, e.g. through a registry mirror
+    pub fn pin_as(
+        &self,
+        written: &ImageReference,
+        reference: ImageReference,
+    ) -> Result<ImageReference, TestcontainerError> {
+        // Recording resolves images afresh so that the lockfile picks up new digests
+        if reference.digest().is_some() || self.mode == LockfileMode::Record {
+            return Ok(reference);
+        }
+
+        let key = written.to_string();
+        match (self.digest(&key), self.mode) {
+            (Some(digest), _) => {
+                let mut pinned = reference;
+                let tag = pinned.tag().unwrap_or("latest").to_owned();
+                pinned.set_qualifier(Qualifier::tag_and_digest(tag, digest));
+                debug!("Pinned {} to {}", key, pinned);
+                Ok(pinned)
+            }
+            (None, LockfileMode::Strict) => Err(TestcontainerError::Generic {
+                message: format!(
+                    "Image {} is not pinned in {}; run with {}=record to add it",
+                    reference.canonical(),
+                    self.path.display(),
+                    TESTCONTAINERS_LOCKFILE_MODE
+                ),
+            }),
+            (None, _) => Ok(reference),
+        }
+    }
+}
+
+fn parse(path: &Path, contents: &str) -> Result<BTreeMap<String, String>, TestcontainerError> {
+    let mut digests = BTreeMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((reference, digest))
+                if !reference.trim().is_empty() && is_valid_digest(digest.trim()) =>
+            {
+                digests.insert(reference.trim().to_owned(), digest.trim().to_owned());
+            }
+            _ => {
+                return Err(TestcontainerError::Generic {
+                    message: format!(
+                        "Invalid entry on line {} of {}: '{line}'",
+                        number + 1,
+                        path.display()
+                    ),
+                })
+            }
+        }
+    }
+    Ok(digests)
+}
+
+pub fn configure(lockfile: Option<Lockfile>) {
+    *ACTIVE.lock().unwrap() = Some(lockfile);
+}
+
+fn from_environment() -> Result<Option<Lockfile>, TestcontainerError> {
+    let mode = match std::env::var(TESTCONTAINERS_LOCKFILE_MODE) {
+        Ok(value) => match value.to_lowercase().as_str() {
+            "use" => Some(LockfileMode::Use),
+            "record" => Some(LockfileMode::Record),
+            "strict" => Some(LockfileMode::Strict),
+            "off" | "" => return Ok(None),
+            value => {
+                warn!(
+                    "'{}' is not a valid value for {}",
+                    value, TESTCONTAINERS_LOCKFILE_MODE
+                );
+                None
+            }
+        },
+        Err(_) => None,
+    };
+
+    let path = std::env::var_os(TESTCONTAINERS_LOCKFILE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_LOCKFILE));
+
+    match mode {
+        Some(mode) => Lockfile::load(path, mode).map(Some),
+        None if path.is_file() => Lockfile::load(path, LockfileMode::Use).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn with_active<F, T>(f: F) -> Result<Option<T>, TestcontainerError>
+where
+    F: FnOnce(&mut Lockfile) -> Result<T, TestcontainerError>,
+{
+    let mut active = ACTIVE.lock().unwrap();
+    if active.is_none() {
+        *active = Some(from_environment()?);
+    }
+    match active.as_mut().and_then(Option::as_mut) {
+        Some(lockfile) => f(lockfile).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn pin(
+    written: &ImageReference,
+    reference: ImageReference,
+) -> Result<ImageReference, TestcontainerError> {
+    let unpinned = reference.clone();
+    Ok(with_active(move |lockfile| lockfile.pin_as(written, reference))?.unwrap_or(unpinned))
+}
+
+// Recording pulls every image once, even when it is cached, so that stale digests are replaced
+// by the registry's current ones
+pub(crate) fn needs_refresh(reference: &ImageReference) -> Result<bool, TestcontainerError> {
+    let recording = with_active(|lockfile| Ok(lockfile.mode() == LockfileMode::Record))?;
+    if recording != Some(true) || reference.digest().is_some() {
+        return Ok(false);
+    }
+    Ok(REFRESHED.lock().unwrap().insert(reference.to_string()))
+}
+
+pub(crate) async fn record(
+    runtime: &dyn ContainerRuntime,
+    written: &ImageReference,
+    reference: &ImageReference,
+) -> Result<(), TestcontainerError> {
+    let recording = with_active(|lockfile| Ok(lockfile.mode() == LockfileMode::Record))?;
+    if recording != Some(true) || reference.digest().is_some() {
+        return Ok(());
+    }
+
+    let image = runtime.inspect_image(&reference.to_string()).await?;
+    let digest = image
+        .repo_digests
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|repo_digest| {
+            let (name, digest) = repo_digest.split_once('@')?;
+            let name = ImageReference::new(name, reference.qualifier().clone());
+            (name.canonical() == reference.canonical()).then(|| digest.to_owned())
+        });
+
+    match digest {
+        Some(digest) => with_active(|lockfile| {
+            if lockfile.digest(&written.to_string()) != Some(digest.as_str()) {
+                info!(
+                    "Recording {} as {} in {}",
+                    written,
+                    digest,
+                    lockfile.path().display()
+                );
+                lockfile.set_digest(written.to_string(), digest);
+                lockfile.save()?;
+            }
+            Ok(())
+        })
+        .map(|_| ()),
+        None => {
+            debug!("Image {} has no registry digest to record", reference);
+            Ok(())
+        }
+    }
+}
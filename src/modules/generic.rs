@@ -1,7 +1,8 @@
 use crate::container::ContainerSettings;
 use crate::{Container, ContainerHandle, Image, ImageSettings, Qualifier};
-use async_trait::async_trait;
 
+#[derive(Image)]
+#[image(container = GenericContainer)]
 pub struct GenericImage {
     settings: ImageSettings,
 }
@@ -14,39 +15,8 @@ impl GenericImage {
     }
 }
 
-impl Image for GenericImage {
-    type ContainerType = GenericContainer;
-
-    fn settings(&self) -> &ImageSettings {
-        &self.settings
-    }
-
-    fn settings_mut(&mut self) -> &mut ImageSettings {
-        &mut self.settings
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Container)]
 pub struct GenericContainer {
     handle: ContainerHandle,
     settings: ContainerSettings,
 }
-
-#[async_trait]
-impl Container for GenericContainer {
-    fn attach(handle: ContainerHandle, settings: ContainerSettings) -> Self {
-        GenericContainer { handle, settings }
-    }
-
-    fn handle(&self) -> &ContainerHandle {
-        &self.handle
-    }
-
-    fn handle_mut(&mut self) -> &mut ContainerHandle {
-        &mut self.handle
-    }
-
-    fn settings(&self) -> &ContainerSettings {
-        &self.settings
-    }
-}
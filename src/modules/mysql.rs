@@ -1,6 +1,6 @@
 use crate::container::ContainerSettings;
 use crate::tasks::MatchLogOutput;
-use crate::{Container, ContainerHandle, Image, ImageSettings, ServiceContainer};
+use crate::{Container, ContainerHandle, Image, ImageSettings};
 
 const IMAGE_NAME: &str = "mysql";
 const DEFAULT_TAG: &str = "latest";
@@ -9,6 +9,8 @@ const MYSQL_USER: &str = "MYSQL_USER";
 const MYSQL_PASSWORD: &str = "MYSQL_PASSWORD";
 const MYSQL_ALLOW_EMPTY_PASSWORD: &str = "MYSQL_ALLOW_EMPTY_PASSWORD";
 
+#[derive(Image)]
+#[image(container = MySqlContainer)]
 pub struct MySqlImage {
     settings: ImageSettings,
 }
@@ -45,44 +47,9 @@ impl MySqlImage {
     }
 }
 
-impl Image for MySqlImage {
-    type ContainerType = MySqlContainer;
-
-    fn settings(&self) -> &ImageSettings {
-        &self.settings
-    }
-
-    fn settings_mut(&mut self) -> &mut ImageSettings {
-        &mut self.settings
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Container)]
+#[service_port("3306/tcp")]
 pub struct MySqlContainer {
     handle: ContainerHandle,
     settings: ContainerSettings,
 }
-
-impl Container for MySqlContainer {
-    fn attach(handle: ContainerHandle, settings: ContainerSettings) -> Self {
-        MySqlContainer { handle, settings }
-    }
-
-    fn handle(&self) -> &ContainerHandle {
-        &self.handle
-    }
-
-    fn handle_mut(&mut self) -> &mut ContainerHandle {
-        &mut self.handle
-    }
-
-    fn settings(&self) -> &ContainerSettings {
-        &self.settings
-    }
-}
-
-impl ServiceContainer for MySqlContainer {
-    fn internal_service_port(&self) -> &str {
-        "3306/tcp"
-    }
-}
@@ -12,6 +12,8 @@ const POSTGRES_USER: &str = "POSTGRES_USER";
 const POSTGRES_PASSWORD: &str = "POSTGRES_PASSWORD";
 const POSTGRES_HOST_AUTH_METHOD: &str = "POSTGRES_HOST_AUTH_METHOD";
 
+#[derive(Image)]
+#[image(container = PostgresContainer)]
 pub struct PostgresImage {
     settings: ImageSettings,
 }
@@ -53,51 +55,13 @@ impl Default for PostgresImage {
     }
 }
 
-#[async_trait]
-impl Image for PostgresImage {
-    type ContainerType = PostgresContainer;
-
-    fn settings(&self) -> &ImageSettings {
-        &self.settings
-    }
-
-    fn settings_mut(&mut self) -> &mut ImageSettings {
-        &mut self.settings
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Container)]
+#[service_port("5432/tcp")]
 pub struct PostgresContainer {
     handle: ContainerHandle,
     settings: ContainerSettings,
 }
 
-#[async_trait]
-impl Container for PostgresContainer {
-    fn attach(handle: ContainerHandle, settings: ContainerSettings) -> Self {
-        PostgresContainer { handle, settings }
-    }
-
-    fn handle(&self) -> &ContainerHandle {
-        &self.handle
-    }
-
-    fn handle_mut(&mut self) -> &mut ContainerHandle {
-        &mut self.handle
-    }
-
-    fn settings(&self) -> &ContainerSettings {
-        &self.settings
-    }
-}
-
-#[async_trait]
-impl ServiceContainer for PostgresContainer {
-    fn internal_service_port(&self) -> &str {
-        "5432/tcp"
-    }
-}
-
 #[async_trait]
 impl DatabaseContainer for PostgresContainer {
     async fn protocol(&self) -> Result<&str, TestcontainerError> {
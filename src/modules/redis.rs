@@ -1,12 +1,12 @@
-use async_trait::async_trait;
-
 use crate::container::ContainerSettings;
 use crate::tasks::MatchLogOutput;
-use crate::{Container, ContainerHandle, Image, ImageSettings, ServiceContainer};
+use crate::{Container, ContainerHandle, Image, ImageSettings};
 
 const IMAGE_NAME: &str = "redis";
 const DEFAULT_TAG: &str = "latest";
 
+#[derive(Image)]
+#[image(container = RedisContainer)]
 pub struct RedisImage {
     settings: ImageSettings,
 }
@@ -20,46 +20,9 @@ impl Default for RedisImage {
     }
 }
 
-#[async_trait]
-impl Image for RedisImage {
-    type ContainerType = RedisContainer;
-
-    fn settings(&self) -> &ImageSettings {
-        &self.settings
-    }
-
-    fn settings_mut(&mut self) -> &mut ImageSettings {
-        &mut self.settings
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Container)]
+#[service_port("6379/tcp")]
 pub struct RedisContainer {
     handle: ContainerHandle,
     settings: ContainerSettings,
 }
-
-impl Container for RedisContainer {
-    fn attach(handle: ContainerHandle, settings: ContainerSettings) -> Self {
-        RedisContainer { handle, settings }
-    }
-
-    fn handle(&self) -> &ContainerHandle {
-        &self.handle
-    }
-
-    fn handle_mut(&mut self) -> &mut ContainerHandle {
-        &mut self.handle
-    }
-
-    fn settings(&self) -> &ContainerSettings {
-        &self.settings
-    }
-}
-
-#[async_trait]
-impl ServiceContainer for RedisContainer {
-    fn internal_service_port(&self) -> &str {
-        "6379/tcp"
-    }
-}
@@ -0,0 +1,182 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use futures::{future, stream, StreamExt, TryStreamExt};
+use hyper::Body;
+use log::{debug, info};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::bollard::image::{CreateImageOptions, ImportImageOptions};
+use crate::bollard::Docker;
+use crate::executor;
+use crate::substitution;
+use crate::{ImageSettings, TestcontainerError};
+
+pub const TESTCONTAINERS_IMAGE_ARCHIVE_DIR: &str = "TESTCONTAINERS_IMAGE_ARCHIVE_DIR";
+
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn archive_file_name(fullname: &str) -> String {
+    let name: String = fullname
+        .chars()
+        .map(|c| match c {
+            '/' | ':' | '@' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{name}.tar")
+}
+
+pub fn archive_dir() -> Option<PathBuf> {
+    std::env::var_os(TESTCONTAINERS_IMAGE_ARCHIVE_DIR)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+pub(crate) fn find_archive(settings: &ImageSettings) -> Option<PathBuf> {
+    if let Some(archive) = settings.archive() {
+        return Some(archive.to_owned());
+    }
+
+    archive_dir()
+        .map(|directory| directory.join(archive_file_name(&settings.resolved_fullname())))
+        .filter(|archive| archive.is_file())
+}
+
+pub async fn load_archive<P: AsRef<Path>>(
+    docker: &Docker,
+    archive: P,
+) -> Result<(), TestcontainerError> {
+    let archive = archive.as_ref();
+    info!("Loading image archive {}", archive.display());
+    executor::compat(import_archive(docker, archive)).await
+}
+
+// Archives can be several gigabytes, so they are streamed to the daemon rather than read whole
+async fn import_archive(docker: &Docker, archive: &Path) -> Result<(), TestcontainerError> {
+    let file = tokio::fs::File::open(archive).await?;
+    let chunks = stream::try_unfold(file, |mut file| async move {
+        let mut chunk = vec![0; ARCHIVE_CHUNK_SIZE];
+        let read = future::poll_fn(|cx| {
+            let mut buf = ReadBuf::new(&mut chunk);
+            Pin::new(&mut file)
+                .poll_read(cx, &mut buf)
+                .map_ok(|()| buf.filled().len())
+        })
+        .await?;
+        if read == 0 {
+            return Ok::<_, std::io::Error>(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((chunk, file)))
+    });
+
+    let body = Body::wrap_stream(chunks);
+    let mut output = docker.import_image(ImportImageOptions { quiet: true }, body, None);
+    while let Some(info) = output.next().await {
+        let info = info?;
+        if let Some(stream) = info.stream {
+            debug!("[load {}] {}", archive.display(), stream.trim());
+        }
+        if let Some(message) = info.error {
+            return Err(TestcontainerError::Generic {
+                message: format!(
+                    "Failed to load image archive {}: {message}",
+                    archive.display()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub async fn export_image<P: AsRef<Path>>(
+    docker: &Docker,
+    fullname: &str,
+    directory: P,
+) -> Result<PathBuf, TestcontainerError> {
+    let fullname = substitution::substitute(fullname);
+    let fullname = fullname.as_str();
+    if docker.inspect_image(fullname).await.is_err() {
+        info!("Pulling image {}", fullname);
+        docker
+            .create_image(
+                Some(CreateImageOptions {
+                    from_image: fullname,
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )
+            .try_collect::<Vec<_>>()
+            .await?;
+    }
+
+    let path = directory.as_ref().join(archive_file_name(fullname));
+    info!("Exporting image {} to {}", fullname, path.display());
+
+    // The archive is written beside its final path and moved into place once complete, so an
+    // interrupted export never leaves a truncated archive behind to be loaded later
+    let temporary = path.with_extension(format!("tar.{}.tmp", std::process::id()));
+    let result = executor::compat(write_archive(
+        docker,
+        fullname,
+        directory.as_ref(),
+        &temporary,
+    ))
+    .await;
+    let result = match result {
+        Ok(()) => {
+            let (temporary, path) = (temporary.clone(), path.clone());
+            executor::spawn_blocking(move || std::fs::rename(temporary, path))
+                .await?
+                .map_err(Into::into)
+        }
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        let _ = executor::spawn_blocking(move || std::fs::remove_file(temporary)).await;
+    }
+    result.map(|()| path)
+}
+
+// File writes are blocking, so each chunk is written off the executor's threads
+async fn write_archive(
+    docker: &Docker,
+    fullname: &str,
+    directory: &Path,
+    temporary: &Path,
+) -> Result<(), TestcontainerError> {
+    let (directory, temporary) = (directory.to_owned(), temporary.to_owned());
+    let mut file = executor::spawn_blocking(move || {
+        std::fs::create_dir_all(directory)?;
+        std::fs::File::create(temporary)
+    })
+    .await??;
+
+    let mut output = docker.export_image(fullname);
+    while let Some(chunk) = output.next().await {
+        let chunk = chunk?;
+        file = executor::spawn_blocking(move || file.write_all(&chunk).map(|()| file)).await??;
+    }
+    executor::spawn_blocking(move || file.sync_all()).await??;
+    Ok(())
+}
+
+pub async fn export_images<I, S, P>(
+    docker: &Docker,
+    fullnames: I,
+    directory: P,
+) -> Result<Vec<PathBuf>, TestcontainerError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    P: AsRef<Path>,
+{
+    let mut archives = Vec::new();
+    for fullname in fullnames {
+        archives.push(export_image(docker, fullname.as_ref(), directory.as_ref()).await?);
+    }
+    Ok(archives)
+}
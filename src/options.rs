@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::bollard::container::Config;
+use crate::bollard::models::HostConfig;
+use crate::TestcontainerError;
+
+const HOST_GATEWAY: &str = "host-gateway";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkMode {
+    Bridge,
+    Host,
+    None,
+    Container(String),
+    Named(String),
+}
+
+impl NetworkMode {
+    // Host ports are only published for containers with a network namespace of their own
+    pub fn publishes_ports(&self) -> bool {
+        matches!(self, NetworkMode::Bridge | NetworkMode::Named(_))
+    }
+}
+
+impl Display for NetworkMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkMode::Bridge => write!(f, "bridge"),
+            NetworkMode::Host => write!(f, "host"),
+            NetworkMode::None => write!(f, "none"),
+            NetworkMode::Container(id) => write!(f, "container:{id}"),
+            NetworkMode::Named(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl From<&str> for NetworkMode {
+    fn from(value: &str) -> Self {
+        match value {
+            "bridge" | "default" => NetworkMode::Bridge,
+            "host" => NetworkMode::Host,
+            "none" => NetworkMode::None,
+            value => match value.strip_prefix("container:") {
+                Some(id) => NetworkMode::Container(id.to_owned()),
+                None => NetworkMode::Named(value.to_owned()),
+            },
+        }
+    }
+}
+
+impl From<String> for NetworkMode {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeOptions {
+    tty: bool,
+    privileged: bool,
+    cap_add: Vec<String>,
+    cap_drop: Vec<String>,
+    security_opt: Vec<String>,
+    user: Option<String>,
+    working_dir: Option<String>,
+    extra_hosts: BTreeMap<String, String>,
+    dns: Vec<String>,
+    sysctls: BTreeMap<String, String>,
+    init: Option<bool>,
+    read_only_rootfs: bool,
+    network_mode: Option<NetworkMode>,
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        RuntimeOptions {
+            tty: true,
+            privileged: false,
+            cap_add: Default::default(),
+            cap_drop: Default::default(),
+            security_opt: Default::default(),
+            user: Default::default(),
+            working_dir: Default::default(),
+            extra_hosts: Default::default(),
+            dns: Default::default(),
+            sysctls: Default::default(),
+            init: Default::default(),
+            read_only_rootfs: false,
+            network_mode: Default::default(),
+        }
+    }
+}
+
+impl RuntimeOptions {
+    pub fn new() -> RuntimeOptions {
+        Default::default()
+    }
+
+    pub fn tty(&self) -> bool {
+        self.tty
+    }
+
+    pub fn with_tty(mut self, tty: bool) -> Self {
+        self.tty = tty;
+        self
+    }
+
+    pub fn privileged(&self) -> bool {
+        self.privileged
+    }
+
+    pub fn with_privileged(mut self, privileged: bool) -> Self {
+        self.privileged = privileged;
+        self
+    }
+
+    pub fn cap_add(&self) -> &[String] {
+        &self.cap_add
+    }
+
+    pub fn with_cap_add<C: Into<String>>(mut self, capability: C) -> Self {
+        self.cap_add.push(capability.into());
+        self
+    }
+
+    pub fn cap_drop(&self) -> &[String] {
+        &self.cap_drop
+    }
+
+    pub fn with_cap_drop<C: Into<String>>(mut self, capability: C) -> Self {
+        self.cap_drop.push(capability.into());
+        self
+    }
+
+    pub fn security_opt(&self) -> &[String] {
+        &self.security_opt
+    }
+
+    pub fn with_security_opt<S: Into<String>>(mut self, option: S) -> Self {
+        self.security_opt.push(option.into());
+        self
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    pub fn with_user<U: Into<String>>(mut self, user: U) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn working_dir(&self) -> Option<&str> {
+        self.working_dir.as_deref()
+    }
+
+    pub fn with_working_dir<W: Into<String>>(mut self, working_dir: W) -> Self {
+        self.working_dir = Some(working_dir.into());
+        self
+    }
+
+    pub fn extra_hosts(&self) -> &BTreeMap<String, String> {
+        &self.extra_hosts
+    }
+
+    pub fn with_extra_host<H: Into<String>, A: Into<String>>(
+        mut self,
+        host: H,
+        address: A,
+    ) -> Self {
+        self.extra_hosts.insert(host.into(), address.into());
+        self
+    }
+
+    // Resolves to the Docker host, e.g. `host.docker.internal` on Linux daemons
+    pub fn with_host_gateway<H: Into<String>>(self, host: H) -> Self {
+        self.with_extra_host(host, HOST_GATEWAY)
+    }
+
+    pub fn dns(&self) -> &[String] {
+        &self.dns
+    }
+
+    pub fn with_dns<D: Into<String>>(mut self, server: D) -> Self {
+        self.dns.push(server.into());
+        self
+    }
+
+    pub fn sysctls(&self) -> &BTreeMap<String, String> {
+        &self.sysctls
+    }
+
+    pub fn with_sysctl<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.sysctls.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn init(&self) -> Option<bool> {
+        self.init
+    }
+
+    pub fn with_init(mut self, init: bool) -> Self {
+        self.init = Some(init);
+        self
+    }
+
+    pub fn read_only_rootfs(&self) -> bool {
+        self.read_only_rootfs
+    }
+
+    pub fn with_read_only_rootfs(mut self, read_only_rootfs: bool) -> Self {
+        self.read_only_rootfs = read_only_rootfs;
+        self
+    }
+
+    pub fn network_mode(&self) -> Option<&NetworkMode> {
+        self.network_mode.as_ref()
+    }
+
+    pub fn with_network_mode<N: Into<NetworkMode>>(mut self, network_mode: N) -> Self {
+        self.network_mode = Some(network_mode.into());
+        self
+    }
+
+    pub(crate) fn apply(&self, config: &mut Config<String>) -> Result<(), TestcontainerError> {
+        let network_mode = self.network_mode.clone().unwrap_or(NetworkMode::Bridge);
+        if let NetworkMode::Container(_) = network_mode {
+            // The container joins another's network stack, so its names and resolvers are shared
+            let conflicts = [
+                ("hostname", config.hostname.is_some()),
+                ("domainname", config.domainname.is_some()),
+                ("dns", !self.dns.is_empty()),
+                ("extra hosts", !self.extra_hosts.is_empty()),
+            ];
+            if let Some((option, _)) = conflicts.iter().find(|(_, set)| *set) {
+                return Err(TestcontainerError::NetworkModeConflict {
+                    option: (*option).to_owned(),
+                    network_mode: network_mode.to_string(),
+                });
+            }
+        }
+
+        config.tty = Some(self.tty);
+        config.user = self.user.clone();
+        config.working_dir = self.working_dir.clone();
+
+        let host_config = config.host_config.get_or_insert_with(HostConfig::default);
+        host_config.privileged = Some(self.privileged).filter(|privileged| *privileged);
+        host_config.cap_add = non_empty(&self.cap_add);
+        host_config.cap_drop = non_empty(&self.cap_drop);
+        host_config.security_opt = non_empty(&self.security_opt);
+        host_config.dns = non_empty(&self.dns);
+        host_config.init = self.init;
+        host_config.readonly_rootfs = Some(self.read_only_rootfs).filter(|read_only| *read_only);
+        host_config.network_mode = self.network_mode.as_ref().map(ToString::to_string);
+        host_config.publish_all_ports = Some(network_mode.publishes_ports());
+        if !self.extra_hosts.is_empty() {
+            host_config.extra_hosts = Some(
+                self.extra_hosts
+                    .iter()
+                    .map(|(host, address)| format!("{host}:{address}"))
+                    .collect(),
+            );
+        }
+        if !self.sysctls.is_empty() {
+            host_config.sysctls = Some(self.sysctls.clone().into_iter().collect());
+        }
+        Ok(())
+    }
+}
+
+fn non_empty(values: &[String]) -> Option<Vec<String>> {
+    (!values.is_empty()).then(|| values.to_vec())
+}
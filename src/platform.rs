@@ -0,0 +1,154 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use log::warn;
+
+use crate::bollard::models::Image as ImageInspect;
+use crate::runtime::ContainerRuntime;
+use crate::TestcontainerError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Platform {
+    os: String,
+    architecture: String,
+    variant: Option<String>,
+}
+
+impl Platform {
+    pub fn new<O: Into<String>, A: Into<String>>(os: O, architecture: A) -> Platform {
+        Platform {
+            os: os.into().to_lowercase(),
+            architecture: normalize_architecture(&architecture.into()),
+            variant: None,
+        }
+    }
+
+    pub fn parse(platform: &str) -> Result<Platform, TestcontainerError> {
+        let invalid = || TestcontainerError::Generic {
+            message: format!("'{platform}' is not a valid platform; expected os/arch[/variant]"),
+        };
+
+        let mut parts = platform.split('/');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(os), Some(arch), variant, None) if !os.is_empty() && !arch.is_empty() => {
+                let mut parsed = Platform::new(os, arch);
+                match variant {
+                    Some("") => return Err(invalid()),
+                    Some(variant) => parsed.variant = Some(variant.to_lowercase()),
+                    None => {}
+                }
+                Ok(parsed)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn os(&self) -> &str {
+        &self.os
+    }
+
+    pub fn architecture(&self) -> &str {
+        &self.architecture
+    }
+
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
+    pub fn with_variant<V: Into<String>>(mut self, variant: V) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+
+    pub(crate) fn of_image(image: &ImageInspect) -> Platform {
+        Platform::new(image.os.as_str(), image.architecture.as_str())
+    }
+
+    // Image inspection does not report the variant, so only os and architecture are compared
+    pub(crate) fn matches(&self, other: &Platform) -> bool {
+        self.os == other.os && self.architecture == other.architecture
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.variant {
+            Some(variant) => write!(f, "{}/{}/{}", self.os, self.architecture, variant),
+            None => write!(f, "{}/{}", self.os, self.architecture),
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = TestcontainerError;
+
+    fn from_str(platform: &str) -> Result<Self, Self::Err> {
+        Platform::parse(platform)
+    }
+}
+
+impl TryFrom<&str> for Platform {
+    type Error = TestcontainerError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Platform::parse(value)
+    }
+}
+
+impl TryFrom<String> for Platform {
+    type Error = TestcontainerError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Platform::parse(&value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlatformCheck {
+    Ignore,
+    #[default]
+    Warn,
+    Fail,
+}
+
+fn normalize_architecture(architecture: &str) -> String {
+    match architecture.to_lowercase().as_str() {
+        "x86_64" | "x86-64" => "amd64".to_owned(),
+        "aarch64" => "arm64".to_owned(),
+        "i386" | "i686" => "386".to_owned(),
+        architecture => architecture.to_owned(),
+    }
+}
+
+pub(crate) async fn verify(
+    runtime: &dyn ContainerRuntime,
+    fullname: &str,
+    requested: Option<&Platform>,
+    check: PlatformCheck,
+) -> Result<(), TestcontainerError> {
+    // Without a requested platform the daemon picks its own, so there is nothing to verify
+    let expected = match requested {
+        Some(requested) if check != PlatformCheck::Ignore => requested,
+        _ => return Ok(()),
+    };
+
+    let actual = Platform::of_image(&runtime.inspect_image(fullname).await?);
+    if expected.matches(&actual) {
+        return Ok(());
+    }
+
+    match check {
+        PlatformCheck::Fail => Err(TestcontainerError::PlatformMismatch {
+            image: fullname.to_owned(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }),
+        _ => {
+            warn!(
+                "Image {} is built for {}, but {} was expected",
+                fullname, actual, expected
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::bollard::image::CreateImageOptions;
+use crate::{Qualifier, TestcontainerError};
+
+const DOCKER_HUB: &str = "docker.io";
+const LEGACY_DOCKER_HUB: &str = "index.docker.io";
+const OFFICIAL_NAMESPACE: &str = "library";
+const DEFAULT_TAG: &str = "latest";
+const MAX_NAME_LENGTH: usize = 255;
+const MAX_TAG_LENGTH: usize = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageReference {
+    name: String,
+    qualifier: Qualifier,
+}
+
+impl ImageReference {
+    pub fn new<N: Into<String>, Q: Into<Qualifier>>(name: N, qualifier: Q) -> ImageReference {
+        ImageReference {
+            name: name.into(),
+            qualifier: qualifier.into(),
+        }
+    }
+
+    pub fn parse(reference: &str) -> Result<ImageReference, TestcontainerError> {
+        let (remainder, digest) = match reference.split_once('@') {
+            Some((remainder, digest)) => (remainder, Some(digest)),
+            None => (reference, None),
+        };
+
+        let (name, tag) = match remainder.rfind(':') {
+            Some(index) if !remainder[index..].contains('/') => {
+                (&remainder[..index], Some(&remainder[index + 1..]))
+            }
+            _ => (remainder, None),
+        };
+
+        let qualifier = match (tag, digest) {
+            (Some(tag), Some(digest)) => Qualifier::tag_and_digest(tag, digest),
+            (None, Some(digest)) => Qualifier::digest(digest),
+            (Some(tag), None) => Qualifier::tag(tag),
+            (None, None) => Qualifier::tag(DEFAULT_TAG),
+        };
+
+        let parsed = ImageReference::new(name, qualifier);
+        parsed
+            .check()
+            .map_err(|reason| TestcontainerError::InvalidImageReference {
+                reference: reference.to_owned(),
+                reason,
+            })?;
+        Ok(parsed)
+    }
+
+    pub fn validate(&self) -> Result<(), TestcontainerError> {
+        self.check()
+            .map_err(|reason| TestcontainerError::InvalidImageReference {
+                reference: self.to_string(),
+                reason,
+            })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("the repository name is empty".to_owned());
+        }
+        if self.name.len() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "the repository name is longer than {MAX_NAME_LENGTH} characters"
+            ));
+        }
+        if let Some(domain) = self.domain() {
+            if !is_valid_domain(domain) {
+                return Err(format!("'{domain}' is not a valid registry host"));
+            }
+        }
+        for component in self.path().split('/') {
+            if !is_valid_path_component(component) {
+                return Err(format!(
+                    "'{component}' is not a valid repository path component; \
+                    only lowercase letters, digits and separators are allowed"
+                ));
+            }
+        }
+        if let Some(tag) = self.tag() {
+            if !is_valid_tag(tag) {
+                return Err(format!("'{tag}' is not a valid tag"));
+            }
+        }
+        if let Some(digest) = self.digest() {
+            if !is_valid_digest(digest) {
+                return Err(format!("'{digest}' is not a valid digest"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn domain(&self) -> Option<&str> {
+        match self.name.split_once('/') {
+            Some((first, _))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                Some(first)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn registry(&self) -> &str {
+        match self.domain() {
+            None | Some(LEGACY_DOCKER_HUB) => DOCKER_HUB,
+            Some(domain) => domain,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self.domain() {
+            Some(domain) => &self.name[domain.len() + 1..],
+            None => &self.name,
+        }
+    }
+
+    pub fn repository(&self) -> String {
+        if self.is_docker_hub() && !self.path().contains('/') {
+            format!("{OFFICIAL_NAMESPACE}/{}", self.path())
+        } else {
+            self.path().to_owned()
+        }
+    }
+
+    pub fn qualifier(&self) -> &Qualifier {
+        &self.qualifier
+    }
+
+    pub fn set_qualifier<Q: Into<Qualifier>>(&mut self, qualifier: Q) -> &mut Self {
+        self.qualifier = qualifier.into();
+        self
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.qualifier.tag_value()
+    }
+
+    pub fn digest(&self) -> Option<&str> {
+        self.qualifier.digest_value()
+    }
+
+    pub fn is_docker_hub(&self) -> bool {
+        self.registry() == DOCKER_HUB
+    }
+
+    pub fn without_domain(&self) -> String {
+        format!("{}{}", self.path(), self.qualifier.suffix())
+    }
+
+    pub fn tagged(&self) -> String {
+        format!("{}:{}", self.name, self.tag().unwrap_or(DEFAULT_TAG))
+    }
+
+    pub fn canonical(&self) -> String {
+        format!(
+            "{}/{}{}",
+            self.registry(),
+            self.repository(),
+            self.qualifier.suffix()
+        )
+    }
+
+    pub(crate) fn pull_options(&self) -> CreateImageOptions<String> {
+        // A digest pins the pull, and is given as part of the image with no tag
+        match self.digest() {
+            Some(digest) => CreateImageOptions {
+                from_image: format!("{}@{digest}", self.name),
+                ..Default::default()
+            },
+            None => CreateImageOptions {
+                from_image: self.name.clone(),
+                tag: self.tag().unwrap_or(DEFAULT_TAG).to_owned(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Display for ImageReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.name, self.qualifier.suffix())
+    }
+}
+
+impl FromStr for ImageReference {
+    type Err = TestcontainerError;
+
+    fn from_str(reference: &str) -> Result<Self, Self::Err> {
+        ImageReference::parse(reference)
+    }
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let (host, port) = match domain.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (domain, None),
+    };
+    if let Some(port) = port {
+        if port.is_empty() || port.parse::<u16>().is_err() {
+            return false;
+        }
+    }
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn is_valid_path_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    if bytes.is_empty() || !is_lower_alnum(bytes[0]) || !is_lower_alnum(bytes[bytes.len() - 1]) {
+        return false;
+    }
+
+    let mut index = 0;
+    while index < bytes.len() {
+        if is_lower_alnum(bytes[index]) {
+            index += 1;
+            continue;
+        }
+        // Separators are '.', '_', '__' or any run of '-'
+        let start = index;
+        while index < bytes.len() && !is_lower_alnum(bytes[index]) {
+            index += 1;
+        }
+        let separator = &component[start..index];
+        let valid = separator == "."
+            || separator == "_"
+            || separator == "__"
+            || separator.chars().all(|c| c == '-');
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_lower_alnum(byte: u8) -> bool {
+    byte.is_ascii_lowercase() || byte.is_ascii_digit()
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    let mut chars = tag.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphanumeric() || first == '_' => {}
+        _ => return false,
+    }
+    tag.len() <= MAX_TAG_LENGTH
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+pub(crate) fn is_valid_digest(digest: &str) -> bool {
+    let (algorithm, encoded) = match digest.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let valid_algorithm = !algorithm.is_empty()
+        && algorithm.split(['+', '.', '_', '-']).all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic())
+                && part.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    let valid_encoding = encoded.len() >= 32 && encoded.chars().all(|c| c.is_ascii_hexdigit());
+    let valid_length = match algorithm {
+        "sha256" => encoded.len() == 64,
+        "sha512" => encoded.len() == 128,
+        _ => true,
+    };
+    valid_algorithm && valid_encoding && valid_length
+}
@@ -0,0 +1,188 @@
+use crate::bollard::models::{HostConfig, ResourcesUlimits};
+use crate::{ContainerHandle, TestcontainerError};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    memory: Option<i64>,
+    memory_swap: Option<i64>,
+    cpu_quota: Option<i64>,
+    cpu_period: Option<i64>,
+    cpu_shares: Option<i64>,
+    cpuset_cpus: Option<String>,
+    shm_size: Option<usize>,
+    ulimits: Vec<Ulimit>,
+    pids_limit: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ulimit {
+    name: String,
+    soft: i64,
+    hard: i64,
+}
+
+impl Ulimit {
+    pub fn new<N: Into<String>>(name: N, soft: i64, hard: i64) -> Ulimit {
+        Ulimit {
+            name: name.into(),
+            soft,
+            hard,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn soft(&self) -> i64 {
+        self.soft
+    }
+
+    pub fn hard(&self) -> i64 {
+        self.hard
+    }
+}
+
+impl ResourceLimits {
+    pub fn new() -> ResourceLimits {
+        Default::default()
+    }
+
+    pub fn memory(&self) -> Option<i64> {
+        self.memory
+    }
+
+    pub fn with_memory(mut self, bytes: i64) -> Self {
+        self.memory = Some(bytes);
+        self
+    }
+
+    pub fn memory_swap(&self) -> Option<i64> {
+        self.memory_swap
+    }
+
+    // Covers memory and swap together; -1 allows unlimited swap
+    pub fn with_memory_swap(mut self, bytes: i64) -> Self {
+        self.memory_swap = Some(bytes);
+        self
+    }
+
+    pub fn cpu_quota(&self) -> Option<i64> {
+        self.cpu_quota
+    }
+
+    pub fn with_cpu_quota(mut self, quota: i64) -> Self {
+        self.cpu_quota = Some(quota);
+        self
+    }
+
+    pub fn cpu_period(&self) -> Option<i64> {
+        self.cpu_period
+    }
+
+    pub fn with_cpu_period(mut self, period: i64) -> Self {
+        self.cpu_period = Some(period);
+        self
+    }
+
+    pub fn cpu_shares(&self) -> Option<i64> {
+        self.cpu_shares
+    }
+
+    pub fn with_cpu_shares(mut self, shares: i64) -> Self {
+        self.cpu_shares = Some(shares);
+        self
+    }
+
+    pub fn cpuset_cpus(&self) -> Option<&str> {
+        self.cpuset_cpus.as_deref()
+    }
+
+    pub fn with_cpuset_cpus<C: Into<String>>(mut self, cpus: C) -> Self {
+        self.cpuset_cpus = Some(cpus.into());
+        self
+    }
+
+    pub fn shm_size(&self) -> Option<usize> {
+        self.shm_size
+    }
+
+    pub fn with_shm_size(mut self, bytes: usize) -> Self {
+        self.shm_size = Some(bytes);
+        self
+    }
+
+    pub fn ulimits(&self) -> &[Ulimit] {
+        &self.ulimits
+    }
+
+    pub fn with_ulimit(mut self, ulimit: Ulimit) -> Self {
+        self.ulimits.retain(|existing| existing.name != ulimit.name);
+        self.ulimits.push(ulimit);
+        self
+    }
+
+    pub fn pids_limit(&self) -> Option<i64> {
+        self.pids_limit
+    }
+
+    pub fn with_pids_limit(mut self, limit: i64) -> Self {
+        self.pids_limit = Some(limit);
+        self
+    }
+
+    pub(crate) fn apply(&self, host_config: &mut HostConfig) {
+        host_config.memory = self.memory;
+        host_config.memory_swap = self.memory_swap;
+        host_config.cpu_quota = self.cpu_quota;
+        host_config.cpu_period = self.cpu_period;
+        host_config.cpu_shares = self.cpu_shares;
+        host_config.cpuset_cpus = self.cpuset_cpus.clone();
+        host_config.shm_size = self.shm_size;
+        host_config.pids_limit = self.pids_limit;
+        if !self.ulimits.is_empty() {
+            host_config.ulimits = Some(
+                self.ulimits
+                    .iter()
+                    .map(|ulimit| ResourcesUlimits {
+                        name: Some(ulimit.name.clone()),
+                        soft: Some(ulimit.soft),
+                        hard: Some(ulimit.hard),
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+// Startup failures are usually reported by a task that stopped seeing output, which hides the
+// fact that the kernel killed the container
+pub(crate) async fn explain_startup_failure(
+    handle: &ContainerHandle,
+    image: &str,
+    error: TestcontainerError,
+) -> TestcontainerError {
+    let inspect = match handle.runtime().inspect_container(handle.id()).await {
+        Ok(inspect) => inspect,
+        Err(_) => return error,
+    };
+
+    let oom_killed = inspect
+        .state
+        .and_then(|state| state.oom_killed)
+        .unwrap_or(false);
+    if oom_killed {
+        TestcontainerError::OutOfMemory {
+            id: handle.id()[..12].to_owned(),
+            image: image.to_owned(),
+            memory: inspect
+                .host_config
+                .and_then(|host_config| host_config.memory)
+                .filter(|memory| *memory > 0)
+                .map(|memory| format!("{memory} bytes"))
+                .unwrap_or_else(|| "no limit".to_owned()),
+        }
+    } else {
+        error
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use log::{debug, info};
+use sha2::{Digest, Sha256};
+
+use crate::bollard::container::Config;
+use crate::runtime::ContainerRuntime;
+use crate::{ContainerHandle, TestcontainerError};
+
+pub const TESTCONTAINERS_REUSE_ENABLE: &str = "TESTCONTAINERS_REUSE_ENABLE";
+pub(crate) const REUSE_HASH_LABEL: &str = "org.testcontainers.reuse-hash";
+const LABEL_PREFIX: &str = "org.testcontainers.";
+
+pub fn is_enabled() -> bool {
+    match std::env::var(TESTCONTAINERS_REUSE_ENABLE) {
+        Ok(value) => matches!(value.to_lowercase().as_str(), "true" | "1" | "yes"),
+        Err(_) => false,
+    }
+}
+
+pub(crate) fn config_hash(config: &Config<String>) -> Result<String, TestcontainerError> {
+    // Labels managed by this library vary between runs and must not affect the hash
+    let mut config = config.clone();
+    if let Some(labels) = config.labels.as_mut() {
+        labels.retain(|key, _| !key.starts_with(LABEL_PREFIX));
+    }
+
+    // Serializing through a Value sorts map keys, keeping the hash stable
+    let value = serde_json::to_value(&config).map_err(|err| TestcontainerError::Generic {
+        message: format!("Unable to hash container configuration: {err}"),
+    })?;
+    Ok(format!("{:x}", Sha256::digest(value.to_string())))
+}
+
+pub(crate) async fn find_running(
+    runtime: &dyn ContainerRuntime,
+    hash: &str,
+) -> Result<Option<String>, TestcontainerError> {
+    let mut filters = HashMap::new();
+    filters.insert(
+        "label".to_owned(),
+        vec![format!("{REUSE_HASH_LABEL}={hash}")],
+    );
+    filters.insert("status".to_owned(), vec!["running".to_owned()]);
+
+    let containers = runtime.list_containers(filters).await?;
+
+    let id = containers.into_iter().next();
+    match &id {
+        Some(id) => info!("Reusing container {} ({})", &id[..12], &hash[..12]),
+        None => debug!("No running container to reuse for {}", &hash[..12]),
+    }
+    Ok(id)
+}
+
+pub(crate) async fn ensure_running(handle: &ContainerHandle) -> Result<(), TestcontainerError> {
+    if handle.state().await?.running {
+        Ok(())
+    } else {
+        Err(TestcontainerError::Generic {
+            message: format!("Container {} is not running", &handle.id()[..12]),
+        })
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::bollard::container::{
+    Config, CreateContainerOptions, DownloadFromContainerOptions, InspectContainerOptions,
+    KillContainerOptions, ListContainersOptions, LogsOptions, RemoveContainerOptions,
+    StopContainerOptions, UploadToContainerOptions,
+};
+use crate::bollard::exec::{CreateExecOptions, StartExecResults};
+use crate::bollard::image::CreateImageOptions;
+use crate::bollard::models::{
+    ContainerInspectResponse, Image as ImageInspect, SystemEventsResponse,
+};
+use crate::bollard::system::EventsOptions;
+use crate::bollard::Docker;
+use crate::executor::compat;
+use crate::{async_trait, Platform, TestcontainerError};
+
+#[cfg(all(unix, feature = "test-support"))]
+pub mod engine;
+#[cfg(feature = "test-support")]
+pub mod fake;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecOutput {
+    pub exit_code: Option<i64>,
+    pub output: String,
+}
+
+// Failures are reported as the bollard errors the Docker daemon would produce, so that callers
+// interpret them the same way whichever runtime is in use
+#[async_trait]
+pub trait ContainerRuntime: Send + Sync + Debug {
+    fn docker(&self) -> Option<&Docker> {
+        None
+    }
+
+    async fn ping(&self) -> Result<(), TestcontainerError>;
+
+    async fn daemon_platform(&self) -> Result<Option<Platform>, TestcontainerError>;
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect, TestcontainerError>;
+
+    async fn pull_image(
+        &self,
+        options: CreateImageOptions<String>,
+    ) -> Result<(), TestcontainerError>;
+
+    async fn create_container(
+        &self,
+        name: Option<&str>,
+        config: Config<String>,
+    ) -> Result<String, TestcontainerError>;
+
+    async fn start_container(&self, id: &str) -> Result<(), TestcontainerError>;
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), TestcontainerError>;
+
+    async fn restart_container(&self, id: &str) -> Result<(), TestcontainerError>;
+
+    async fn pause_container(&self, id: &str) -> Result<(), TestcontainerError>;
+
+    async fn unpause_container(&self, id: &str) -> Result<(), TestcontainerError>;
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), TestcontainerError>;
+
+    async fn remove_container(&self, id: &str) -> Result<(), TestcontainerError>;
+
+    async fn inspect_container(
+        &self,
+        id: &str,
+    ) -> Result<ContainerInspectResponse, TestcontainerError>;
+
+    async fn list_containers(
+        &self,
+        filters: HashMap<String, Vec<String>>,
+    ) -> Result<Vec<String>, TestcontainerError>;
+
+    fn logs(
+        &self,
+        id: &str,
+        follow: bool,
+        tail: Option<usize>,
+    ) -> BoxStream<'static, Result<String, TestcontainerError>>;
+
+    async fn exec(
+        &self,
+        id: &str,
+        cmd: Vec<String>,
+        env: Vec<String>,
+    ) -> Result<ExecOutput, TestcontainerError>;
+
+    // Events from `since` onwards are replayed before new ones are streamed
+    fn events(
+        &self,
+        id: &str,
+        since: SystemTime,
+    ) -> BoxStream<'static, Result<SystemEventsResponse, TestcontainerError>>;
+
+    async fn upload_archive(
+        &self,
+        id: &str,
+        path: &str,
+        archive: Vec<u8>,
+    ) -> Result<(), TestcontainerError>;
+
+    async fn download_archive(&self, id: &str, path: &str) -> Result<Vec<u8>, TestcontainerError>;
+}
+
+// Image builds and archive loading use bollard APIs that only a Docker daemon can serve
+pub(crate) fn require_docker<'a>(
+    runtime: &'a dyn ContainerRuntime,
+    operation: &str,
+) -> Result<&'a Docker, TestcontainerError> {
+    runtime.docker().ok_or_else(|| TestcontainerError::Generic {
+        message: format!("{operation} requires a Docker daemon"),
+    })
+}
+
+#[async_trait]
+impl ContainerRuntime for Docker {
+    fn docker(&self) -> Option<&Docker> {
+        Some(self)
+    }
+
+    async fn ping(&self) -> Result<(), TestcontainerError> {
+        compat(Docker::ping(self)).await?;
+        Ok(())
+    }
+
+    async fn daemon_platform(&self) -> Result<Option<Platform>, TestcontainerError> {
+        let version = compat(self.version()).await?;
+        Ok(match (version.os, version.arch) {
+            (Some(os), Some(arch)) => Some(Platform::new(os, arch)),
+            _ => None,
+        })
+    }
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect, TestcontainerError> {
+        Ok(compat(Docker::inspect_image(self, name)).await?)
+    }
+
+    async fn pull_image(
+        &self,
+        options: CreateImageOptions<String>,
+    ) -> Result<(), TestcontainerError> {
+        compat(self.create_image(Some(options), None, None))
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        name: Option<&str>,
+        config: Config<String>,
+    ) -> Result<String, TestcontainerError> {
+        let options = name.map(|name| CreateContainerOptions { name });
+        Ok(compat(Docker::create_container(self, options, config))
+            .await?
+            .id)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        compat(Docker::start_container::<String>(self, id, None)).await?;
+        Ok(())
+    }
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), TestcontainerError> {
+        let options = timeout.map(|timeout| StopContainerOptions {
+            t: timeout.as_secs() as i64,
+        });
+        compat(Docker::stop_container(self, id, options)).await?;
+        Ok(())
+    }
+
+    async fn restart_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        compat(Docker::restart_container(self, id, None)).await?;
+        Ok(())
+    }
+
+    async fn pause_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        compat(Docker::pause_container(self, id)).await?;
+        Ok(())
+    }
+
+    async fn unpause_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        compat(Docker::unpause_container(self, id)).await?;
+        Ok(())
+    }
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), TestcontainerError> {
+        compat(Docker::kill_container(
+            self,
+            id,
+            Some(KillContainerOptions { signal }),
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let options = RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        };
+        compat(Docker::remove_container(self, id, Some(options))).await?;
+        Ok(())
+    }
+
+    async fn inspect_container(
+        &self,
+        id: &str,
+    ) -> Result<ContainerInspectResponse, TestcontainerError> {
+        Ok(compat(Docker::inspect_container(
+            self,
+            id,
+            None::<InspectContainerOptions>,
+        ))
+        .await?)
+    }
+
+    async fn list_containers(
+        &self,
+        filters: HashMap<String, Vec<String>>,
+    ) -> Result<Vec<String>, TestcontainerError> {
+        let containers = compat(Docker::list_containers(
+            self,
+            Some(ListContainersOptions {
+                filters,
+                ..Default::default()
+            }),
+        ))
+        .await?;
+        Ok(containers
+            .into_iter()
+            .filter_map(|container| container.id)
+            .collect())
+    }
+
+    fn logs(
+        &self,
+        id: &str,
+        follow: bool,
+        tail: Option<usize>,
+    ) -> BoxStream<'static, Result<String, TestcontainerError>> {
+        let options = LogsOptions {
+            follow,
+            stdout: true,
+            stderr: true,
+            tail: tail
+                .map(|tail| tail.to_string())
+                .unwrap_or_else(|| "all".to_owned()),
+            ..Default::default()
+        };
+        compat(Docker::logs(self, id, Some(options)))
+            .map(|output| Ok(output?.to_string()))
+            .boxed()
+    }
+
+    async fn exec(
+        &self,
+        id: &str,
+        cmd: Vec<String>,
+        env: Vec<String>,
+    ) -> Result<ExecOutput, TestcontainerError> {
+        let exec = compat(self.create_exec(
+            id,
+            CreateExecOptions {
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                cmd: Some(cmd),
+                env: Some(env),
+                ..Default::default()
+            },
+        ))
+        .await?
+        .id;
+
+        // Output is printed as it arrives, so long-running commands can be followed live
+        let mut output = String::new();
+        if let StartExecResults::Attached { output: stream, .. } =
+            compat(self.start_exec(&exec, None)).await?
+        {
+            let mut stream = compat(stream);
+            while let Some(Ok(message)) = stream.next().await {
+                print!("{message}");
+                output.push_str(&message.to_string());
+            }
+        }
+
+        let exit_code = compat(self.inspect_exec(&exec)).await?.exit_code;
+        Ok(ExecOutput { exit_code, output })
+    }
+
+    fn events(
+        &self,
+        id: &str,
+        since: SystemTime,
+    ) -> BoxStream<'static, Result<SystemEventsResponse, TestcontainerError>> {
+        let mut filters = HashMap::new();
+        filters.insert("type".to_owned(), vec!["container".to_owned()]);
+        filters.insert("container".to_owned(), vec![id.to_owned()]);
+
+        // bollard sends nanoseconds without zero padding, so only whole seconds are sent intact
+        let seconds = since
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let since = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(seconds));
+        compat(Docker::events(
+            self,
+            Some(EventsOptions {
+                since: Some(since),
+                filters,
+                ..Default::default()
+            }),
+        ))
+        .map(|event| Ok(event?))
+        .boxed()
+    }
+
+    async fn upload_archive(
+        &self,
+        id: &str,
+        path: &str,
+        archive: Vec<u8>,
+    ) -> Result<(), TestcontainerError> {
+        let options = UploadToContainerOptions {
+            path,
+            ..Default::default()
+        };
+        compat(self.upload_to_container(id, Some(options), archive.into())).await?;
+        Ok(())
+    }
+
+    async fn download_archive(&self, id: &str, path: &str) -> Result<Vec<u8>, TestcontainerError> {
+        let chunks =
+            compat(self.download_from_container(id, Some(DownloadFromContainerOptions { path })))
+                .try_collect::<Vec<_>>()
+                .await?;
+        Ok(chunks.concat())
+    }
+}
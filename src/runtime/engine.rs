@@ -0,0 +1,681 @@
+use std::collections::HashMap;
+use std::os::unix::net::UnixListener as StdUnixListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::channel::oneshot;
+use futures::future;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::bollard::container::Config;
+use crate::bollard::errors::Error as BollardError;
+use crate::bollard::image::CreateImageOptions;
+use crate::bollard::models::{ContainerConfig, HostConfig, SystemVersion};
+use crate::bollard::{Docker, API_DEFAULT_VERSION};
+use crate::runtime::fake::FakeRuntime;
+use crate::runtime::ContainerRuntime;
+use crate::TestcontainerError;
+
+const CLIENT_TIMEOUT: u64 = 120;
+const STDOUT: u8 = 1;
+
+static ENGINES: AtomicUsize = AtomicUsize::new(0);
+
+// Serves the Docker Engine API from a FakeRuntime, so that requests go through bollard's own
+// serialization. The server runs on its own thread, because containers dropped on a
+// current-thread runtime block that thread while they are cleaned up.
+#[derive(Debug)]
+pub struct FakeEngine {
+    socket_path: PathBuf,
+    runtime: FakeRuntime,
+    state: Arc<Mutex<EngineState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug, Default)]
+struct EngineState {
+    responses: Vec<ScriptedResponse>,
+    requests: Vec<String>,
+    execs: HashMap<String, Exec>,
+}
+
+#[derive(Debug)]
+struct ScriptedResponse {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+#[derive(Clone, Debug)]
+struct Exec {
+    container: String,
+    cmd: Vec<String>,
+    env: Vec<String>,
+    exit_code: Option<i64>,
+}
+
+impl FakeEngine {
+    pub fn start(runtime: FakeRuntime) -> Result<FakeEngine, TestcontainerError> {
+        let socket_path = std::env::temp_dir().join(format!(
+            "testcontainers-engine-{}-{}.sock",
+            std::process::id(),
+            ENGINES.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = StdUnixListener::bind(&socket_path)?;
+        listener.set_nonblocking(true)?;
+
+        let state = Arc::new(Mutex::new(EngineState::default()));
+        let server = Server {
+            runtime: runtime.clone(),
+            state: state.clone(),
+        };
+        let (shutdown, stopped) = oneshot::channel();
+        let thread = std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async move {
+                let listener = match UnixListener::from_std(listener) {
+                    Ok(listener) => listener,
+                    Err(err) => return warn!("Fake engine cannot listen: {err}"),
+                };
+                let accept = async {
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _)) => {
+                                tokio::spawn(server.clone().serve(stream));
+                            }
+                            Err(err) => return warn!("Fake engine stopped accepting: {err}"),
+                        }
+                    }
+                };
+                future::select(Box::pin(accept), stopped).await;
+            });
+        });
+
+        debug!("Fake engine listening on {}", socket_path.display());
+        Ok(FakeEngine {
+            socket_path,
+            runtime,
+            state,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    // Scripted responses take precedence over the runtime, for every matching request
+    pub fn with_response<M, P, B>(self, method: M, path: P, status: u16, body: B) -> Self
+    where
+        M: Into<String>,
+        P: Into<String>,
+        B: Into<String>,
+    {
+        self.state.lock().unwrap().responses.push(ScriptedResponse {
+            method: method.into(),
+            path: path.into(),
+            status,
+            body: body.into(),
+        });
+        self
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub fn runtime(&self) -> &FakeRuntime {
+        &self.runtime
+    }
+
+    pub fn docker(&self) -> Result<Docker, TestcontainerError> {
+        Ok(Docker::connect_with_unix(
+            &self.socket_path.to_string_lossy(),
+            CLIENT_TIMEOUT,
+            API_DEFAULT_VERSION,
+        )?)
+    }
+
+    // Requests as `METHOD /path`, without the API version prefix or query
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for FakeEngine {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+#[derive(Clone)]
+struct Server {
+    runtime: FakeRuntime,
+    state: Arc<Mutex<EngineState>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+enum Response {
+    Full {
+        status: u16,
+        content_type: &'static str,
+        body: Vec<u8>,
+    },
+    Stream {
+        content_type: &'static str,
+        chunks: BoxStream<'static, Vec<u8>>,
+    },
+    Upgrade {
+        output: Vec<u8>,
+    },
+}
+
+impl Server {
+    async fn serve(self, stream: UnixStream) {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let request = match read_request(&mut stream).await {
+                Ok(Some(request)) => request,
+                Ok(None) => return,
+                Err(err) => return debug!("Fake engine could not read a request: {err}"),
+            };
+            let response = self.route(request).await;
+            let upgraded = matches!(response, Response::Upgrade { .. });
+            if let Err(err) = write_response(stream.get_mut(), response).await {
+                return debug!("Fake engine could not write a response: {err}");
+            }
+            // Upgraded connections carry a single raw stream, which ends when they close
+            if upgraded {
+                let _ = stream.get_mut().shutdown().await;
+                return;
+            }
+        }
+    }
+
+    async fn route(&self, request: Request) -> Response {
+        let scripted = {
+            let mut state = self.state.lock().unwrap();
+            state
+                .requests
+                .push(format!("{} {}", request.method, request.path));
+            state
+                .responses
+                .iter()
+                .find(|response| response.method == request.method && response.path == request.path)
+                .map(|response| Response::Full {
+                    status: response.status,
+                    content_type: "application/json",
+                    body: response.body.clone().into_bytes(),
+                })
+        };
+        if let Some(response) = scripted {
+            return response;
+        }
+
+        match self.handle(request).await {
+            Ok(response) => response,
+            Err(err) => error_response(err),
+        }
+    }
+
+    async fn handle(&self, request: Request) -> Result<Response, TestcontainerError> {
+        let query = &request.query;
+        let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+        let runtime = &self.runtime;
+
+        let response = match (request.method.as_str(), segments.as_slice()) {
+            ("GET" | "HEAD", ["_ping"]) => text(200, "OK"),
+            ("GET", ["version"]) => {
+                let platform = runtime.daemon_platform().await?;
+                json_response(
+                    200,
+                    &SystemVersion {
+                        os: platform.as_ref().map(|platform| platform.os().to_owned()),
+                        arch: platform.map(|platform| platform.architecture().to_owned()),
+                        ..Default::default()
+                    },
+                )
+            }
+            ("GET", ["images", name @ .., "json"]) => {
+                json_response(200, &runtime.inspect_image(&name.join("/")).await?)
+            }
+            ("POST", ["images", "create"]) => {
+                runtime
+                    .pull_image(CreateImageOptions {
+                        from_image: query.get("fromImage").cloned().unwrap_or_default(),
+                        tag: query.get("tag").cloned().unwrap_or_default(),
+                        platform: query.get("platform").cloned().unwrap_or_default(),
+                        ..Default::default()
+                    })
+                    .await?;
+                json_lines(vec![json!({ "status": "Pull complete" })])
+            }
+            ("POST", ["containers", "create"]) => {
+                let body: Value = serde_json::from_slice(&request.body).map_err(invalid)?;
+                let host_config: Option<HostConfig> = match body.get("HostConfig") {
+                    Some(host_config) => {
+                        Some(serde_json::from_value(host_config.clone()).map_err(invalid)?)
+                    }
+                    None => None,
+                };
+                let config: ContainerConfig = serde_json::from_value(body).map_err(invalid)?;
+                let config = Config {
+                    host_config,
+                    ..Config::from(config)
+                };
+                let name = query.get("name").map(String::as_str);
+                let id = runtime.create_container(name, config).await?;
+                json_response(201, &json!({ "Id": id, "Warnings": [] }))
+            }
+            ("GET", ["containers", "json"]) => {
+                let filters = match query.get("filters") {
+                    Some(filters) => serde_json::from_str(filters).map_err(invalid)?,
+                    None => HashMap::new(),
+                };
+                let containers: Vec<Value> = runtime
+                    .list_containers(filters)
+                    .await?
+                    .into_iter()
+                    .map(|id| json!({ "Id": id }))
+                    .collect();
+                json_response(200, &containers)
+            }
+            ("GET", ["containers", id, "json"]) => {
+                json_response(200, &runtime.inspect_container(id).await?)
+            }
+            ("POST", ["containers", id, "start"]) => {
+                runtime.start_container(id).await?;
+                no_content()
+            }
+            ("POST", ["containers", id, "stop"]) => {
+                let timeout = query
+                    .get("t")
+                    .and_then(|timeout| timeout.parse().ok())
+                    .map(Duration::from_secs);
+                runtime.stop_container(id, timeout).await?;
+                no_content()
+            }
+            ("POST", ["containers", id, "restart"]) => {
+                runtime.restart_container(id).await?;
+                no_content()
+            }
+            ("POST", ["containers", id, "pause"]) => {
+                runtime.pause_container(id).await?;
+                no_content()
+            }
+            ("POST", ["containers", id, "unpause"]) => {
+                runtime.unpause_container(id).await?;
+                no_content()
+            }
+            ("POST", ["containers", id, "kill"]) => {
+                let signal = query.get("signal").map(String::as_str).unwrap_or("SIGKILL");
+                runtime.kill_container(id, signal).await?;
+                no_content()
+            }
+            ("DELETE", ["containers", id]) => {
+                runtime.remove_container(id).await?;
+                no_content()
+            }
+            ("GET", ["containers", id, "logs"]) => {
+                let follow = matches!(query.get("follow").map(String::as_str), Some("true" | "1"));
+                let tail = query.get("tail").and_then(|tail| tail.parse().ok());
+                Response::Stream {
+                    content_type: "application/vnd.docker.raw-stream",
+                    chunks: runtime
+                        .logs(id, follow, tail)
+                        .take_while(|line| future::ready(line.is_ok()))
+                        .map(|line| frame(line.unwrap_or_default().as_bytes()))
+                        .boxed(),
+                }
+            }
+            ("POST", ["containers", id, "exec"]) => {
+                let body: Value = serde_json::from_slice(&request.body).map_err(invalid)?;
+                let strings = |key: &str| -> Vec<String> {
+                    serde_json::from_value(body.get(key).cloned().unwrap_or_default())
+                        .unwrap_or_default()
+                };
+                let (cmd, env) = (strings("Cmd"), strings("Env"));
+                let container = runtime.inspect_container(id).await?.id.unwrap_or_default();
+
+                let mut state = self.state.lock().unwrap();
+                let exec_id = format!("exec{:060}", state.execs.len());
+                state.execs.insert(
+                    exec_id.clone(),
+                    Exec {
+                        container,
+                        cmd,
+                        env,
+                        exit_code: None,
+                    },
+                );
+                json_response(201, &json!({ "Id": exec_id }))
+            }
+            ("POST", ["exec", exec_id, "start"]) => {
+                let exec = self.exec(exec_id)?;
+                let output = runtime
+                    .exec(&exec.container, exec.cmd.clone(), exec.env.clone())
+                    .await?;
+                if let Some(exec) = self.state.lock().unwrap().execs.get_mut(*exec_id) {
+                    exec.exit_code = output.exit_code;
+                }
+                Response::Upgrade {
+                    output: frame(output.output.as_bytes()),
+                }
+            }
+            ("GET", ["exec", exec_id, "json"]) => {
+                let exec = self.exec(exec_id)?;
+                json_response(
+                    200,
+                    &json!({
+                        "ID": exec_id,
+                        "ContainerID": exec.container,
+                        "Running": false,
+                        "ExitCode": exec.exit_code,
+                    }),
+                )
+            }
+            ("GET", ["events"]) => {
+                let filters: HashMap<String, Vec<String>> = match query.get("filters") {
+                    Some(filters) => serde_json::from_str(filters).map_err(invalid)?,
+                    None => HashMap::new(),
+                };
+                let id = filters
+                    .get("container")
+                    .and_then(|containers| containers.first())
+                    .ok_or_else(|| invalid("events are only served for a single container"))?;
+                // Without `since` the daemon only streams events that happen from now on
+                let since = match query.get("since").filter(|since| !since.is_empty()) {
+                    Some(since) => parse_timestamp(since).ok_or_else(|| invalid("bad since"))?,
+                    None => SystemTime::now(),
+                };
+                Response::Stream {
+                    content_type: "application/json",
+                    chunks: runtime
+                        .events(id, since)
+                        .take_while(|event| future::ready(event.is_ok()))
+                        .map(|event| json_line(&event.unwrap_or_default()))
+                        .boxed(),
+                }
+            }
+            ("PUT", ["containers", id, "archive"]) => {
+                let path = query.get("path").map(String::as_str).unwrap_or("/");
+                runtime.upload_archive(id, path, request.body).await?;
+                text(200, "")
+            }
+            ("GET", ["containers", id, "archive"]) => {
+                let path = query.get("path").map(String::as_str).unwrap_or("/");
+                Response::Full {
+                    status: 200,
+                    content_type: "application/x-tar",
+                    body: runtime.download_archive(id, path).await?,
+                }
+            }
+            _ => {
+                return Err(BollardError::DockerResponseNotFoundError {
+                    message: format!("page not found: {} {}", request.method, request.path),
+                }
+                .into())
+            }
+        };
+        Ok(response)
+    }
+
+    fn exec(&self, exec_id: &str) -> Result<Exec, TestcontainerError> {
+        self.state
+            .lock()
+            .unwrap()
+            .execs
+            .get(exec_id)
+            .cloned()
+            .ok_or_else(|| {
+                BollardError::DockerResponseNotFoundError {
+                    message: format!("No such exec instance: {exec_id}"),
+                }
+                .into()
+            })
+    }
+}
+
+async fn read_request(
+    stream: &mut BufReader<UnixStream>,
+) -> Result<Option<Request>, std::io::Error> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default().to_owned();
+
+    let mut content_length = 0;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "transfer-encoding" => chunked = value.to_lowercase().contains("chunked"),
+                _ => (),
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+    if chunked {
+        body = read_chunked(stream).await?;
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    // Requests are prefixed with the API version, e.g. `/v1.41/containers/json`
+    let path = match path
+        .strip_prefix("/v")
+        .and_then(|path| path.split_once('/'))
+    {
+        Some((version, path)) if version.chars().all(|c| c.is_ascii_digit() || c == '.') => {
+            format!("/{path}")
+        }
+        _ => path.to_owned(),
+    };
+
+    Ok(Some(Request {
+        method,
+        path,
+        query: form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect(),
+        body,
+    }))
+}
+
+async fn read_chunked(stream: &mut BufReader<UnixStream>) -> Result<Vec<u8>, std::io::Error> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await?;
+        let size = usize::from_str_radix(line.trim().split(';').next().unwrap_or("0"), 16)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        if size == 0 {
+            line.clear();
+            stream.read_line(&mut line).await?;
+            return Ok(body);
+        }
+        let mut chunk = vec![0; size + 2];
+        stream.read_exact(&mut chunk).await?;
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+async fn write_response(stream: &mut UnixStream, response: Response) -> Result<(), std::io::Error> {
+    match response {
+        Response::Full {
+            status,
+            content_type,
+            body,
+        } => {
+            // Responses without content must not announce any
+            let head = if status == 204 || status == 304 {
+                format!("HTTP/1.1 {status} {}\r\n\r\n", reason(status))
+            } else {
+                format!(
+                    "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+                    reason(status),
+                    body.len()
+                )
+            };
+            stream.write_all(head.as_bytes()).await?;
+            if status != 204 && status != 304 {
+                stream.write_all(&body).await?;
+            }
+        }
+        Response::Stream {
+            content_type,
+            mut chunks,
+        } => {
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nTransfer-Encoding: chunked\r\n\r\n"
+            );
+            stream.write_all(head.as_bytes()).await?;
+            stream.flush().await?;
+            while let Some(chunk) = chunks.next().await {
+                if chunk.is_empty() {
+                    continue;
+                }
+                stream
+                    .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                    .await?;
+                stream.write_all(&chunk).await?;
+                stream.write_all(b"\r\n").await?;
+                stream.flush().await?;
+            }
+            stream.write_all(b"0\r\n\r\n").await?;
+        }
+        Response::Upgrade { output } => {
+            stream
+                .write_all(
+                    b"HTTP/1.1 101 UPGRADED\r\nContent-Type: application/vnd.docker.raw-stream\r\nConnection: Upgrade\r\nUpgrade: tcp\r\n\r\n",
+                )
+                .await?;
+            stream.write_all(&output).await?;
+        }
+    }
+    stream.flush().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+fn error_response(error: TestcontainerError) -> Response {
+    let (status, message) = match error {
+        TestcontainerError::DockerError { source } => match source {
+            BollardError::DockerResponseNotFoundError { message } => (404, message),
+            BollardError::DockerResponseConflictError { message } => (409, message),
+            BollardError::DockerResponseNotModifiedError { message } => (304, message),
+            BollardError::DockerResponseBadParameterError { message } => (400, message),
+            BollardError::DockerResponseServerError {
+                status_code,
+                message,
+            } => (status_code, message),
+            source => (500, source.to_string()),
+        },
+        error => (500, error.to_string()),
+    };
+    json_response(status, &json!({ "message": message }))
+}
+
+// Timestamps are sent as seconds since the epoch with an optional fraction, e.g. `1700000000.0`
+fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    let (seconds, fraction) = timestamp.split_once('.').unwrap_or((timestamp, "0"));
+    let seconds = Duration::from_secs(seconds.parse().ok()?);
+    let nanos = Duration::from_nanos(fraction.parse().ok()?);
+    Some(UNIX_EPOCH + seconds + nanos)
+}
+
+fn invalid<E: ToString>(error: E) -> TestcontainerError {
+    BollardError::DockerResponseBadParameterError {
+        message: error.to_string(),
+    }
+    .into()
+}
+
+fn no_content() -> Response {
+    text(204, "")
+}
+
+fn text(status: u16, body: &str) -> Response {
+    Response::Full {
+        status,
+        content_type: "text/plain",
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+fn json_response<T: serde::Serialize>(status: u16, value: &T) -> Response {
+    Response::Full {
+        status,
+        content_type: "application/json",
+        body: serde_json::to_vec(value).unwrap_or_default(),
+    }
+}
+
+fn json_lines(values: Vec<Value>) -> Response {
+    Response::Stream {
+        content_type: "application/json",
+        chunks: stream::iter(values.iter().map(json_line).collect::<Vec<_>>()).boxed(),
+    }
+}
+
+fn json_line<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    let mut line = serde_json::to_vec(value).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+// Output without a TTY is multiplexed into frames with an 8 byte header: the stream, three
+// bytes of padding and the big-endian payload length
+fn frame(payload: &[u8]) -> Vec<u8> {
+    if payload.is_empty() {
+        return Vec::new();
+    }
+    let mut frame = vec![STDOUT, 0, 0, 0];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
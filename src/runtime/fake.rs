@@ -0,0 +1,746 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::bollard::container::Config;
+use crate::bollard::errors::Error as BollardError;
+use crate::bollard::image::CreateImageOptions;
+use crate::bollard::models::{
+    ContainerConfig, ContainerInspectResponse, ContainerState, ContainerStateStatusEnum,
+    Image as ImageInspect, NetworkSettings, PortBinding, PortMap, SystemEventsResponse,
+    SystemEventsResponseActor,
+};
+use crate::runtime::{ContainerRuntime, ExecOutput};
+use crate::{async_trait, Platform, TestcontainerError};
+
+const FIRST_HOST_PORT: u16 = 32768;
+// The fake has no clock, so every timestamp it reports is the same
+const FAKE_TIME: &str = "2000-01-01T00:00:00.000000000Z";
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+#[derive(Clone, Debug)]
+pub struct FakeImage {
+    exposed_ports: Vec<String>,
+    log_lines: Vec<String>,
+    exit_code: Option<i64>,
+    oom_killed: bool,
+    start_failure: Option<String>,
+    platform: Platform,
+}
+
+impl Default for FakeImage {
+    fn default() -> Self {
+        FakeImage {
+            exposed_ports: Default::default(),
+            log_lines: Default::default(),
+            exit_code: Default::default(),
+            oom_killed: false,
+            start_failure: Default::default(),
+            platform: Platform::new("linux", "amd64"),
+        }
+    }
+}
+
+impl FakeImage {
+    pub fn new() -> FakeImage {
+        Default::default()
+    }
+
+    pub fn with_exposed_port<P: Into<String>>(mut self, port: P) -> Self {
+        self.exposed_ports.push(port.into());
+        self
+    }
+
+    pub fn with_log_line<L: Into<String>>(mut self, line: L) -> Self {
+        self.log_lines.push(line.into());
+        self
+    }
+
+    pub fn with_exit_on_start(mut self, exit_code: i64) -> Self {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    pub fn with_oom_on_start(mut self) -> Self {
+        self.oom_killed = true;
+        self.exit_code = Some(137);
+        self
+    }
+
+    pub fn with_start_failure<M: Into<String>>(mut self, message: M) -> Self {
+        self.start_failure = Some(message.into());
+        self
+    }
+
+    pub fn with_platform<P: Into<Platform>>(mut self, platform: P) -> Self {
+        self.platform = platform.into();
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FakeRuntime {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    registry: HashMap<String, FakeImage>,
+    local: HashMap<String, FakeImage>,
+    pull_failures: HashMap<String, String>,
+    exec_results: HashMap<Vec<String>, ExecOutput>,
+    containers: BTreeMap<String, FakeContainer>,
+    history: Vec<(String, SystemTime, SystemEventsResponse)>,
+    subscribers: Vec<(String, UnboundedSender<SystemEventsResponse>)>,
+    calls: Vec<String>,
+    next_port: u16,
+}
+
+#[derive(Debug)]
+struct FakeContainer {
+    name: Option<String>,
+    image: String,
+    image_id: String,
+    fake_image: FakeImage,
+    config: Config<String>,
+    status: ContainerStateStatusEnum,
+    exit_code: Option<i64>,
+    started: bool,
+    finished: bool,
+    ports: PortMap,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl FakeRuntime {
+    pub fn new() -> FakeRuntime {
+        Default::default()
+    }
+
+    // Images in the registry can be pulled, while local images are present from the start
+    pub fn with_image<N: Into<String>>(self, name: N, image: FakeImage) -> Self {
+        self.lock().registry.insert(name.into(), image);
+        self
+    }
+
+    pub fn with_local_image<N: Into<String>>(self, name: N, image: FakeImage) -> Self {
+        self.lock().local.insert(name.into(), image);
+        self
+    }
+
+    pub fn with_pull_failure<N: Into<String>, M: Into<String>>(self, name: N, message: M) -> Self {
+        self.lock()
+            .pull_failures
+            .insert(name.into(), message.into());
+        self
+    }
+
+    pub fn with_exec_result<I, T, O>(self, cmd: I, exit_code: i64, output: O) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+        O: Into<String>,
+    {
+        self.lock().exec_results.insert(
+            cmd.into_iter().map(Into::into).collect(),
+            ExecOutput {
+                exit_code: Some(exit_code),
+                output: output.into(),
+            },
+        );
+        self
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    pub fn container_ids(&self) -> Vec<String> {
+        self.lock().containers.keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl State {
+    fn record(&mut self, call: String) {
+        self.calls.push(call);
+    }
+
+    // Containers created for a platform refer to their image by id rather than by name
+    fn find_local(&self, name: &str) -> Option<(&str, &FakeImage)> {
+        self.local
+            .iter()
+            .find(|(local, _)| local.as_str() == name || image_id(local) == name)
+            .map(|(local, image)| (local.as_str(), image))
+    }
+
+    fn container(&mut self, id: &str) -> Result<&mut FakeContainer, TestcontainerError> {
+        let id = self.resolve(id)?;
+        Ok(self.containers.get_mut(&id).expect("Resolved container"))
+    }
+
+    fn resolve(&self, id_or_name: &str) -> Result<String, TestcontainerError> {
+        self.containers
+            .iter()
+            .find(|(id, container)| {
+                id.starts_with(id_or_name) || container.name.as_deref() == Some(id_or_name)
+            })
+            .map(|(id, _)| id.clone())
+            .ok_or_else(|| not_found(format!("No such container: {id_or_name}")))
+    }
+
+    fn emit(&mut self, id: &str, action: &str, attributes: HashMap<String, String>) {
+        let now = SystemTime::now();
+        let elapsed = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let event = SystemEventsResponse {
+            typ: Some("container".to_owned()),
+            action: Some(action.to_owned()),
+            actor: Some(SystemEventsResponseActor {
+                id: Some(id.to_owned()),
+                attributes: Some(attributes),
+            }),
+            time: Some(elapsed.as_secs() as i64),
+            time_nano: Some(elapsed.as_nanos() as i64),
+        };
+        self.history.push((id.to_owned(), now, event.clone()));
+        self.subscribers.retain(|(subscribed, sender)| {
+            subscribed != id || sender.unbounded_send(event.clone()).is_ok()
+        });
+    }
+
+    fn start(&mut self, id: &str) -> Result<(), TestcontainerError> {
+        let mut next_port = self.next_port.max(FIRST_HOST_PORT);
+        let container = self.container(id)?;
+        if let Some(message) = &container.fake_image.start_failure {
+            return Err(server_error(message.clone()));
+        }
+
+        // Docker publishes exposed ports on fresh host ports every time a container starts
+        let publish = container
+            .config
+            .host_config
+            .as_ref()
+            .and_then(|host_config| host_config.publish_all_ports)
+            .unwrap_or(false);
+        let exposed = container
+            .fake_image
+            .exposed_ports
+            .iter()
+            .cloned()
+            .chain(
+                container
+                    .config
+                    .exposed_ports
+                    .iter()
+                    .flatten()
+                    .map(|(port, _)| port.clone()),
+            )
+            .collect::<Vec<_>>();
+        container.ports = exposed
+            .into_iter()
+            .map(|port| {
+                let bindings = publish.then(|| {
+                    next_port += 1;
+                    vec![PortBinding {
+                        host_ip: Some("0.0.0.0".to_owned()),
+                        host_port: Some((next_port - 1).to_string()),
+                    }]
+                });
+                (port, bindings)
+            })
+            .collect();
+
+        container.started = true;
+        container.status = ContainerStateStatusEnum::RUNNING;
+        container.exit_code = None;
+        let (exit_code, oom_killed) = (
+            container.fake_image.exit_code,
+            container.fake_image.oom_killed,
+        );
+        self.next_port = next_port;
+        self.emit(id, "start", HashMap::new());
+
+        if oom_killed {
+            self.emit(id, "oom", HashMap::new());
+        }
+        if let Some(exit_code) = exit_code {
+            self.exit(id, exit_code)?;
+        }
+        Ok(())
+    }
+
+    fn exit(&mut self, id: &str, exit_code: i64) -> Result<(), TestcontainerError> {
+        let container = self.container(id)?;
+        container.status = ContainerStateStatusEnum::EXITED;
+        container.exit_code = Some(exit_code);
+        container.finished = true;
+        let attributes = HashMap::from([("exitCode".to_owned(), exit_code.to_string())]);
+        self.emit(id, "die", attributes);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for FakeRuntime {
+    async fn ping(&self) -> Result<(), TestcontainerError> {
+        Ok(())
+    }
+
+    async fn daemon_platform(&self) -> Result<Option<Platform>, TestcontainerError> {
+        self.lock().record("version".to_owned());
+        Ok(Some(FakeImage::default().platform))
+    }
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect, TestcontainerError> {
+        let state = self.lock();
+        let (local, image) = state
+            .find_local(name)
+            .ok_or_else(|| not_found(format!("No such image: {name}")))?;
+        let repository = local.split(['@', ':']).next().unwrap_or_default();
+
+        Ok(ImageInspect {
+            id: image_id(local),
+            repo_tags: Some(vec![local.to_owned()]),
+            repo_digests: Some(vec![format!("{repository}@{}", image_id(local))]),
+            os: image.platform.os().to_owned(),
+            architecture: image.platform.architecture().to_owned(),
+            ..Default::default()
+        })
+    }
+
+    async fn pull_image(
+        &self,
+        options: CreateImageOptions<String>,
+    ) -> Result<(), TestcontainerError> {
+        let name = match options.tag.as_str() {
+            "" => options.from_image.clone(),
+            tag => format!("{}:{tag}", options.from_image),
+        };
+
+        let mut state = self.lock();
+        state.record(format!("pull {name}"));
+        if let Some(message) = state.pull_failures.get(&name) {
+            return Err(server_error(message.clone()));
+        }
+        let image = state
+            .registry
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| not_found(format!("pull access denied for {name}")))?;
+        state.local.insert(name, image);
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        name: Option<&str>,
+        config: Config<String>,
+    ) -> Result<String, TestcontainerError> {
+        let mut state = self.lock();
+        let image = config.image.clone().unwrap_or_default();
+        let (image_id, fake_image) = state
+            .find_local(&image)
+            .map(|(local, fake_image)| (image_id(local), fake_image.clone()))
+            .ok_or_else(|| not_found(format!("No such image: {image}")))?;
+
+        if let Some(name) = name {
+            if state
+                .containers
+                .values()
+                .any(|container| container.name.as_deref() == Some(name))
+            {
+                return Err(BollardError::DockerResponseConflictError {
+                    message: format!("Conflict. The container name \"/{name}\" is already in use"),
+                }
+                .into());
+            }
+        }
+
+        // Docker refuses the same combinations when a container joins another's network stack
+        let host_config = config.host_config.clone().unwrap_or_default();
+        if let Some(target) = host_config
+            .network_mode
+            .as_deref()
+            .and_then(|mode| mode.strip_prefix("container:"))
+        {
+            state.resolve(target)?;
+            if config.hostname.is_some() || host_config.publish_all_ports == Some(true) {
+                return Err(BollardError::DockerResponseBadParameterError {
+                    message: "conflicting options: hostname or port publishing and the container network mode".to_owned(),
+                }
+                .into());
+            }
+        }
+
+        let id = format!(
+            "{:x}",
+            Sha256::digest(format!(
+                "{}|{}",
+                image,
+                state.containers.len() + state.calls.len()
+            ))
+        );
+        state.record(format!("create {image}"));
+        state.containers.insert(
+            id.clone(),
+            FakeContainer {
+                name: name.map(ToOwned::to_owned),
+                image,
+                image_id,
+                fake_image,
+                config,
+                status: ContainerStateStatusEnum::CREATED,
+                exit_code: None,
+                started: false,
+                finished: false,
+                ports: Default::default(),
+                files: Default::default(),
+            },
+        );
+        Ok(id)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("start {}", &id[..12.min(id.len())]));
+        state.start(id)
+    }
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        _: Option<Duration>,
+    ) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("stop {}", &id[..12.min(id.len())]));
+        let container = state.container(id)?;
+        if container.status != ContainerStateStatusEnum::RUNNING
+            && container.status != ContainerStateStatusEnum::PAUSED
+        {
+            return Err(BollardError::DockerResponseNotModifiedError {
+                message: "Container already stopped".to_owned(),
+            }
+            .into());
+        }
+        state.emit(id, "kill", HashMap::new());
+        state.exit(id, 0)?;
+        state.emit(id, "stop", HashMap::new());
+        Ok(())
+    }
+
+    async fn restart_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("restart {}", &id[..12.min(id.len())]));
+        if state.container(id)?.status == ContainerStateStatusEnum::RUNNING {
+            state.exit(id, 0)?;
+        }
+        state.start(id)?;
+        state.emit(id, "restart", HashMap::new());
+        Ok(())
+    }
+
+    async fn pause_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("pause {}", &id[..12.min(id.len())]));
+        let container = state.container(id)?;
+        if container.status != ContainerStateStatusEnum::RUNNING {
+            return Err(conflict(format!("Container {id} is not running")));
+        }
+        container.status = ContainerStateStatusEnum::PAUSED;
+        state.emit(id, "pause", HashMap::new());
+        Ok(())
+    }
+
+    async fn unpause_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("unpause {}", &id[..12.min(id.len())]));
+        let container = state.container(id)?;
+        if container.status != ContainerStateStatusEnum::PAUSED {
+            return Err(server_error(format!("Container {id} is not paused")));
+        }
+        container.status = ContainerStateStatusEnum::RUNNING;
+        state.emit(id, "unpause", HashMap::new());
+        Ok(())
+    }
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("kill {} {signal}", &id[..12.min(id.len())]));
+        if state.container(id)?.status != ContainerStateStatusEnum::RUNNING {
+            return Err(conflict(format!("Container {id} is not running")));
+        }
+        let attributes = HashMap::from([("signal".to_owned(), signal.to_owned())]);
+        state.emit(id, "kill", attributes);
+        // Every signal ends the fake process, with the exit code a shell would report
+        let exit_code = match signal {
+            "SIGKILL" | "KILL" | "9" => 137,
+            _ => 143,
+        };
+        state.exit(id, exit_code)
+    }
+
+    async fn remove_container(&self, id: &str) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("remove {}", &id[..12.min(id.len())]));
+        let id = state.resolve(id)?;
+        state.containers.remove(&id);
+        state.emit(&id, "destroy", HashMap::new());
+        Ok(())
+    }
+
+    async fn inspect_container(
+        &self,
+        id: &str,
+    ) -> Result<ContainerInspectResponse, TestcontainerError> {
+        let mut state = self.lock();
+        let id = state.resolve(id)?;
+        let container = state.container(&id)?;
+        let config = &container.config;
+        let status = container.status;
+
+        Ok(ContainerInspectResponse {
+            id: Some(id.clone()),
+            name: container.name.as_ref().map(|name| format!("/{name}")),
+            created: Some(FAKE_TIME.to_owned()),
+            image: Some(container.image_id.clone()),
+            state: Some(ContainerState {
+                running: Some(
+                    status == ContainerStateStatusEnum::RUNNING
+                        || status == ContainerStateStatusEnum::PAUSED,
+                ),
+                paused: Some(status == ContainerStateStatusEnum::PAUSED),
+                status: Some(status),
+                oom_killed: Some(container.fake_image.oom_killed && container.finished),
+                exit_code: Some(container.exit_code.unwrap_or_default()),
+                started_at: Some(fake_time(container.started)),
+                finished_at: Some(fake_time(container.finished)),
+                ..Default::default()
+            }),
+            config: Some(ContainerConfig {
+                hostname: config.hostname.clone(),
+                domainname: config.domainname.clone(),
+                user: config.user.clone(),
+                tty: config.tty,
+                env: config.env.clone(),
+                cmd: config.cmd.clone(),
+                entrypoint: config.entrypoint.clone(),
+                image: Some(container.image.clone()),
+                working_dir: config.working_dir.clone(),
+                labels: config.labels.clone(),
+                stop_signal: config.stop_signal.clone(),
+                ..Default::default()
+            }),
+            host_config: config.host_config.clone(),
+            network_settings: Some(NetworkSettings {
+                ports: Some(container.ports.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn list_containers(
+        &self,
+        filters: HashMap<String, Vec<String>>,
+    ) -> Result<Vec<String>, TestcontainerError> {
+        let state = self.lock();
+        let labels = filters.get("label").cloned().unwrap_or_default();
+        let statuses = filters.get("status").cloned().unwrap_or_default();
+
+        Ok(state
+            .containers
+            .iter()
+            .filter(|(_, container)| {
+                let container_labels = container.config.labels.clone().unwrap_or_default();
+                labels.iter().all(|filter| match filter.split_once('=') {
+                    Some((key, value)) => {
+                        container_labels.get(key).map(String::as_str) == Some(value)
+                    }
+                    None => container_labels.contains_key(filter),
+                }) && (statuses.is_empty() || statuses.contains(&container.status.to_string()))
+            })
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    // Log streams end once the recorded output is exhausted, as if the container had stopped
+    fn logs(
+        &self,
+        id: &str,
+        _: bool,
+        tail: Option<usize>,
+    ) -> BoxStream<'static, Result<String, TestcontainerError>> {
+        let mut state = self.lock();
+        match tail {
+            Some(tail) => state.record(format!("logs {} --tail {tail}", &id[..12.min(id.len())])),
+            None => state.record(format!("logs {}", &id[..12.min(id.len())])),
+        }
+        let lines = match state.container(id) {
+            Ok(container) if container.started => {
+                let lines = &container.fake_image.log_lines;
+                let skip = tail
+                    .map(|tail| lines.len().saturating_sub(tail))
+                    .unwrap_or(0);
+                lines[skip..]
+                    .iter()
+                    .map(|line| Ok(format!("{line}\n")))
+                    .collect()
+            }
+            Ok(_) => Vec::new(),
+            Err(err) => vec![Err(err)],
+        };
+        stream::iter(lines).boxed()
+    }
+
+    async fn exec(
+        &self,
+        id: &str,
+        cmd: Vec<String>,
+        _: Vec<String>,
+    ) -> Result<ExecOutput, TestcontainerError> {
+        let mut state = self.lock();
+        state.record(format!("exec {}", cmd.join(" ")));
+        if state.container(id)?.status != ContainerStateStatusEnum::RUNNING {
+            return Err(conflict(format!("Container {id} is not running")));
+        }
+        Ok(state.exec_results.get(&cmd).cloned().unwrap_or(ExecOutput {
+            exit_code: Some(0),
+            output: String::new(),
+        }))
+    }
+
+    fn events(
+        &self,
+        id: &str,
+        since: SystemTime,
+    ) -> BoxStream<'static, Result<SystemEventsResponse, TestcontainerError>> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut state = self.lock();
+        let id = state.resolve(id).unwrap_or_else(|_| id.to_owned());
+        for (_, _, event) in state
+            .history
+            .iter()
+            .filter(|(emitted, time, _)| *emitted == id && *time >= since)
+        {
+            let _ = sender.unbounded_send(event.clone());
+        }
+        state.subscribers.push((id, sender));
+        receiver.map(Ok).boxed()
+    }
+
+    async fn upload_archive(
+        &self,
+        id: &str,
+        path: &str,
+        archive: Vec<u8>,
+    ) -> Result<(), TestcontainerError> {
+        let mut state = self.lock();
+        let container = state.container(id)?;
+        let mut archive = Archive::new(archive.as_slice());
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.header().entry_type() != EntryType::Regular {
+                continue;
+            }
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            container
+                .files
+                .insert(format!("{}/{}", path.trim_end_matches('/'), name), contents);
+        }
+        Ok(())
+    }
+
+    async fn download_archive(&self, id: &str, path: &str) -> Result<Vec<u8>, TestcontainerError> {
+        let mut state = self.lock();
+        let container = state.container(id)?;
+        let base = path.trim_end_matches('/');
+        let name = base.rsplit('/').next().unwrap_or_default();
+        let mut builder = Builder::new(Vec::new());
+
+        if let Some(contents) = container.files.get(base) {
+            append(&mut builder, name, EntryType::Regular, contents)?;
+            return Ok(builder.into_inner()?);
+        }
+
+        let prefix = format!("{base}/");
+        let children: BTreeMap<_, _> = container
+            .files
+            .iter()
+            .filter_map(|(file, contents)| {
+                let relative = file.strip_prefix(&prefix)?;
+                Some(match relative.split_once('/') {
+                    Some((directory, _)) => (directory.to_owned(), None),
+                    None => (relative.to_owned(), Some(contents)),
+                })
+            })
+            .collect();
+        if children.is_empty() {
+            return Err(not_found(format!(
+                "Could not find the file {path} in container {id}"
+            )));
+        }
+
+        append(&mut builder, name, EntryType::Directory, &[])?;
+        for (child, contents) in children {
+            let child = format!("{name}/{child}");
+            match contents {
+                Some(contents) => append(&mut builder, &child, EntryType::Regular, contents)?,
+                None => append(&mut builder, &child, EntryType::Directory, &[])?,
+            }
+        }
+        Ok(builder.into_inner()?)
+    }
+}
+
+fn append(
+    builder: &mut Builder<Vec<u8>>,
+    name: &str,
+    entry_type: EntryType,
+    contents: &[u8],
+) -> Result<(), TestcontainerError> {
+    let mut header = Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(if entry_type == EntryType::Directory {
+        0o755
+    } else {
+        0o644
+    });
+    header.set_entry_type(entry_type);
+    builder.append_data(&mut header, name, contents)?;
+    Ok(())
+}
+
+fn fake_time(happened: bool) -> String {
+    if happened { FAKE_TIME } else { ZERO_TIME }.to_owned()
+}
+
+fn image_id(name: &str) -> String {
+    format!("sha256:{:x}", Sha256::digest(name))
+}
+
+fn not_found(message: String) -> TestcontainerError {
+    BollardError::DockerResponseNotFoundError { message }.into()
+}
+
+fn conflict(message: String) -> TestcontainerError {
+    BollardError::DockerResponseConflictError { message }.into()
+}
+
+fn server_error(message: String) -> TestcontainerError {
+    BollardError::DockerResponseServerError {
+        status_code: 500,
+        message,
+    }
+    .into()
+}
@@ -0,0 +1,327 @@
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+
+use futures::{FutureExt, StreamExt};
+use log::warn;
+
+use crate::errors;
+use crate::runtime::ContainerRuntime;
+use crate::{
+    async_trait, Container, ContainerHandle, ContainerStatus, DropAction, Image, TestcontainerError,
+};
+
+const DIAGNOSTIC_LOG_LINES: usize = 100;
+
+#[derive(Clone, Debug, Default)]
+pub struct ContainerDiagnostics {
+    pub id: String,
+    pub image: String,
+    pub status: Option<ContainerStatus>,
+    pub exit_code: Option<i64>,
+    pub oom_killed: bool,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+}
+
+impl ContainerDiagnostics {
+    fn report(&self) -> String {
+        let mut report = format!(
+            "Container {} ({}) {:?}, exit code {:?}{}",
+            &self.id[..12.min(self.id.len())],
+            self.image,
+            self.status,
+            self.exit_code,
+            if self.oom_killed {
+                ", out of memory"
+            } else {
+                ""
+            }
+        );
+        if let Some(error) = &self.error {
+            report.push_str(&format!(": {error}"));
+        }
+        for line in &self.logs {
+            report.push_str(&format!("\n  | {line}"));
+        }
+        report
+    }
+
+    pub(crate) async fn collect(handle: &ContainerHandle, image: String) -> ContainerDiagnostics {
+        let mut diagnostics = ContainerDiagnostics {
+            id: handle.id().to_owned(),
+            image,
+            ..Default::default()
+        };
+
+        match handle.state().await {
+            Ok(info) => {
+                diagnostics.status = Some(info.status);
+                diagnostics.exit_code = info.exit_code;
+                diagnostics.oom_killed = info.oom_killed;
+                diagnostics.error = info.error;
+            }
+            Err(err) => warn!(
+                "Unable to inspect container {}: {}",
+                &handle.id()[..12],
+                errors::Chain(&err)
+            ),
+        }
+
+        let mut logs = handle
+            .runtime()
+            .logs(handle.id(), false, Some(DIAGNOSTIC_LOG_LINES));
+        while let Some(Ok(line)) = logs.next().await {
+            diagnostics.logs.extend(line.lines().map(ToOwned::to_owned));
+        }
+
+        diagnostics
+    }
+}
+
+pub async fn with_container<I, F, Fut, T>(
+    image: I,
+    body: F,
+) -> Result<(T, ContainerDiagnostics), TestcontainerError>
+where
+    I: Image + Sync,
+    F: FnOnce(Arc<I::ContainerType>) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let container = Arc::new(image.start_container().await?);
+    let outcome = AssertUnwindSafe(body(container.clone()))
+        .catch_unwind()
+        .await;
+    let diagnostics = finish(container.handle(), container.settings().fullname()).await;
+
+    match outcome {
+        Ok(output) => Ok((output, diagnostics?)),
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+pub async fn with_containers<S, F, Fut, T>(
+    images: S,
+    body: F,
+) -> Result<(T, Vec<ContainerDiagnostics>), TestcontainerError>
+where
+    S: ImageSet,
+    F: FnOnce(S::Containers) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let containers = images.start_all().await?;
+    let outcome = AssertUnwindSafe(body(containers.clone()))
+        .catch_unwind()
+        .await;
+    let diagnostics = S::finish_all(&containers).await;
+
+    match outcome {
+        Ok(output) => Ok((output, diagnostics?)),
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+// Backs `#[testcontainers_async::test]`: a failed test reports its containers' diagnostics before
+// the failure itself, whether the body returned an error or panicked. The body owns its
+// containers, so only their ids and runtimes are kept back to diagnose and clean them up.
+pub async fn run_test<S, F, Fut>(
+    images: S,
+    runtime: Option<Arc<dyn ContainerRuntime>>,
+    body: F,
+) -> Fut::Output
+where
+    S: ImageSet,
+    F: FnOnce(S::Owned) -> Fut,
+    Fut: Future,
+    Fut::Output: TestOutcome,
+{
+    let mut containers = match images.start_owned_with(runtime).await {
+        Ok(containers) => containers,
+        Err(err) => return Fut::Output::from_error(err),
+    };
+    let remains = S::take_cleanup(&mut containers);
+
+    let outcome = AssertUnwindSafe(body(containers)).catch_unwind().await;
+    let failed = outcome.as_ref().map_or(true, TestOutcome::is_failure);
+    let diagnostics: Result<Vec<_>, _> = futures::future::join_all(
+        remains
+            .iter()
+            .map(|(handle, image)| finish(handle, image.clone())),
+    )
+    .await
+    .into_iter()
+    .collect();
+
+    if failed {
+        for diagnostics in diagnostics.iter().flatten() {
+            eprintln!("{}", diagnostics.report());
+        }
+    }
+    match (outcome, diagnostics) {
+        (Err(panic), _) => std::panic::resume_unwind(panic),
+        (Ok(_), Err(err)) if !failed => Fut::Output::from_error(err),
+        (Ok(output), _) => output,
+    }
+}
+
+pub trait TestOutcome {
+    fn is_failure(&self) -> bool;
+
+    fn from_error(error: TestcontainerError) -> Self;
+}
+
+impl TestOutcome for () {
+    fn is_failure(&self) -> bool {
+        false
+    }
+
+    fn from_error(error: TestcontainerError) -> Self {
+        panic!("{}", errors::Chain(&error))
+    }
+}
+
+impl<T, E: From<TestcontainerError>> TestOutcome for Result<T, E> {
+    fn is_failure(&self) -> bool {
+        self.is_err()
+    }
+
+    fn from_error(error: TestcontainerError) -> Self {
+        Err(error.into())
+    }
+}
+
+async fn finish(
+    handle: &ContainerHandle,
+    image: String,
+) -> Result<ContainerDiagnostics, TestcontainerError> {
+    // Stopping first lets the diagnostics report how the container exited
+    if !matches!(handle.effective_drop_action(), DropAction::Retain) && !handle.is_reusable() {
+        if let Err(err) = handle.runtime().stop_container(handle.id(), None).await {
+            warn!(
+                "Unable to stop container {}: {}",
+                &handle.id()[..12],
+                errors::Chain(&err)
+            );
+        }
+    }
+
+    let diagnostics = ContainerDiagnostics::collect(handle, image).await;
+    handle.cleanup().await?;
+    Ok(diagnostics)
+}
+
+#[async_trait]
+pub trait ImageSet: Send {
+    type Containers: Clone + Send;
+
+    type Owned: Send;
+
+    async fn start_all(self) -> Result<Self::Containers, TestcontainerError>
+    where
+        Self: Sized,
+    {
+        self.start_all_with(None).await
+    }
+
+    // Containers are started on a fresh Docker connection unless a runtime is given
+    async fn start_all_with(
+        self,
+        runtime: Option<Arc<dyn ContainerRuntime>>,
+    ) -> Result<Self::Containers, TestcontainerError>;
+
+    async fn start_owned_with(
+        self,
+        runtime: Option<Arc<dyn ContainerRuntime>>,
+    ) -> Result<Self::Owned, TestcontainerError>;
+
+    // The handles that now clean up each container, with the image it was started from
+    fn take_cleanup(containers: &mut Self::Owned) -> Vec<(ContainerHandle, String)>;
+
+    async fn finish_all(
+        containers: &Self::Containers,
+    ) -> Result<Vec<ContainerDiagnostics>, TestcontainerError>;
+}
+
+macro_rules! impl_image_set {
+    ($($image:ident => $index:tt),+) => {
+        #[async_trait]
+        impl<$($image),+> ImageSet for ($($image,)+)
+        where
+            $($image: Image + Send + Sync, $image::ContainerType: Send + Sync,)+
+        {
+            type Containers = ($(Arc<$image::ContainerType>,)+);
+
+            type Owned = ($($image::ContainerType,)+);
+
+            async fn start_all_with(
+                self,
+                runtime: Option<Arc<dyn ContainerRuntime>>,
+            ) -> Result<Self::Containers, TestcontainerError> {
+                let started = self.start_owned_with(runtime).await?;
+                Ok(($(Arc::new(started.$index),)+))
+            }
+
+            async fn start_owned_with(
+                self,
+                runtime: Option<Arc<dyn ContainerRuntime>>,
+            ) -> Result<Self::Owned, TestcontainerError> {
+                let started = futures::join!($(
+                    async {
+                        match &runtime {
+                            Some(runtime) => {
+                                self.$index.start_container_with_runtime(runtime.clone()).await
+                            }
+                            None => self.$index.start_container().await,
+                        }
+                    }
+                ),+);
+
+                let mut error = None;
+                let started = ($(
+                    match started.$index {
+                        Ok(container) => Some(container),
+                        Err(err) => {
+                            error.get_or_insert(err);
+                            None
+                        }
+                    },
+                )+);
+
+                match error {
+                    None => Ok(($(started.$index.expect("Container started"),)+)),
+                    Some(error) => {
+                        // Containers that did start are cleaned up before reporting the failure
+                        $(
+                            if let Some(container) = &started.$index {
+                                let _ = container.handle().cleanup().await;
+                            }
+                        )+
+                        Err(error)
+                    }
+                }
+            }
+
+            fn take_cleanup(containers: &mut Self::Owned) -> Vec<(ContainerHandle, String)> {
+                vec![$((
+                    containers.$index.handle_mut().take_cleanup(),
+                    containers.$index.settings().fullname(),
+                )),+]
+            }
+
+            async fn finish_all(
+                containers: &Self::Containers,
+            ) -> Result<Vec<ContainerDiagnostics>, TestcontainerError> {
+                let finished = futures::join!($(finish(
+                    containers.$index.handle(),
+                    containers.$index.settings().fullname(),
+                )),+);
+                Ok(vec![$(finished.$index?),+])
+            }
+        }
+    };
+}
+
+impl_image_set!(A => 0);
+impl_image_set!(A => 0, B => 1);
+impl_image_set!(A => 0, B => 1, C => 2);
+impl_image_set!(A => 0, B => 1, C => 2, D => 3);
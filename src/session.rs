@@ -0,0 +1,89 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, Once, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+
+type EndHook = Box<dyn FnOnce() + Send>;
+
+static STATE: Mutex<State> = Mutex::new(State {
+    guards: 0,
+    hooks: Vec::new(),
+});
+static EXIT_HOOK: Once = Once::new();
+static SESSION_ID: OnceLock<String> = OnceLock::new();
+
+struct State {
+    guards: usize,
+    hooks: Vec<EndHook>,
+}
+
+pub fn id() -> &'static str {
+    SESSION_ID.get_or_init(|| {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let digest = Sha256::digest(format!("{}|{}", std::process::id(), started));
+        format!("{digest:x}")[..16].to_owned()
+    })
+}
+
+// Cleanup that outlives a single test, such as containers shared for the session and images built
+// with `with_remove_on_exit`, runs when the last guard is dropped. Whatever is still pending when
+// the process exits, as happens under libtest where nothing outlives the tests, runs at exit.
+#[must_use = "session cleanup runs when the guard is dropped"]
+pub struct SessionGuard {
+    _private: (),
+}
+
+pub fn guard() -> SessionGuard {
+    STATE.lock().unwrap().guards += 1;
+    SessionGuard { _private: () }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let hooks = match STATE.lock() {
+            Ok(mut state) => {
+                state.guards -= 1;
+                if state.guards > 0 {
+                    return;
+                }
+                std::mem::take(&mut state.hooks)
+            }
+            Err(_) => return,
+        };
+        for hook in hooks.into_iter().rev() {
+            hook();
+        }
+    }
+}
+
+pub(crate) fn on_end<F>(hook: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    EXIT_HOOK.call_once(|| {
+        // SAFETY: run_at_exit is a plain function that does not unwind
+        if unsafe { libc::atexit(run_at_exit) } != 0 {
+            warn!("Unable to register session cleanup for process exit");
+        }
+    });
+    STATE.lock().unwrap().hooks.push(Box::new(hook));
+}
+
+// Hooks reach the daemon from threads of their own, which are still free to run during exit
+extern "C" fn run_at_exit() {
+    let hooks = match STATE.lock() {
+        Ok(mut state) => std::mem::take(&mut state.hooks),
+        Err(_) => return,
+    };
+    if !hooks.is_empty() {
+        debug!("Running {} session cleanup hooks at exit", hooks.len());
+    }
+    for hook in hooks.into_iter().rev() {
+        let _ = catch_unwind(AssertUnwindSafe(hook));
+    }
+}
@@ -0,0 +1,200 @@
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use log::{debug, info};
+
+use crate::executor;
+use crate::session;
+use crate::{Container, Image, TestcontainerError};
+
+type SharedAny = Arc<dyn Any + Send + Sync>;
+
+static REGISTRY: Mutex<Option<HashMap<String, Arc<tokio::sync::Mutex<Slot>>>>> = Mutex::new(None);
+static END_HOOK_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct Slot {
+    weak: Option<Weak<dyn Any + Send + Sync>>,
+    retained: Option<SharedAny>,
+}
+
+pub struct SharedContainer<C: Container> {
+    container: Arc<C>,
+    key: Arc<str>,
+}
+
+impl<C> SharedContainer<C>
+where
+    C: Container + Send + Sync + 'static,
+{
+    pub async fn acquire<I>(image: I) -> Result<SharedContainer<C>, TestcontainerError>
+    where
+        I: Image<ContainerType = C> + Send + Sync + 'static,
+    {
+        SharedContainer::acquire_with(image, false).await
+    }
+
+    pub async fn acquire_for_session<I>(image: I) -> Result<SharedContainer<C>, TestcontainerError>
+    where
+        I: Image<ContainerType = C> + Send + Sync + 'static,
+    {
+        SharedContainer::acquire_with(image, true).await
+    }
+
+    async fn acquire_with<I>(
+        image: I,
+        retain: bool,
+    ) -> Result<SharedContainer<C>, TestcontainerError>
+    where
+        I: Image<ContainerType = C> + Send + Sync + 'static,
+    {
+        let key: Arc<str> =
+            format!("{}|{}", type_name::<I>(), image.settings().config_key()).into();
+        let mut slot = loop {
+            let slot = REGISTRY
+                .lock()
+                .unwrap()
+                .get_or_insert_with(Default::default)
+                .entry(key.to_string())
+                .or_default()
+                .clone();
+
+            // Concurrent first uses wait here, so the container is only ever started once. A slot
+            // pruned while waiting is no longer shared, so the lookup starts over.
+            let guard = slot.clone().lock_owned().await;
+            if is_registered(&key, &slot) {
+                break guard;
+            }
+        };
+        if let Some(container) = slot
+            .weak
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .and_then(|container| container.downcast::<C>().ok())
+        {
+            debug!("Sharing container {}", &container.handle().id()[..12]);
+            if retain {
+                slot.retained = Some(container.clone());
+                register_end_hook();
+            }
+            return Ok(SharedContainer { container, key });
+        }
+
+        // Containers are started on a task of their own, so that a test cancelled mid-startup
+        // does not abandon a container that other tests are waiting on
+        let container = executor::spawn(async move { image.start_container().await }).await??;
+        let container = Arc::new(container);
+        info!(
+            "Started shared container {}",
+            &container.handle().id()[..12]
+        );
+
+        let any: SharedAny = container.clone();
+        slot.weak = Some(Arc::downgrade(&any));
+        if retain {
+            slot.retained = Some(any);
+            register_end_hook();
+        }
+
+        Ok(SharedContainer { container, key })
+    }
+
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.container)
+    }
+}
+
+impl<C: Container> Clone for SharedContainer<C> {
+    fn clone(&self) -> Self {
+        SharedContainer {
+            container: self.container.clone(),
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<C: Container> Drop for SharedContainer<C> {
+    fn drop(&mut self) {
+        // The last handle takes the slot with it, so the registry only holds live containers
+        if Arc::strong_count(&self.container) == 1 {
+            prune(&self.key);
+        }
+    }
+}
+
+impl<C: Container> Deref for SharedContainer<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.container
+    }
+}
+
+impl<C: Container> Debug for SharedContainer<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedContainer")
+            .field("container", &self.container)
+            .finish()
+    }
+}
+
+// Retained containers are released when the session ends, after which a new session may retain
+// containers again
+fn register_end_hook() {
+    if END_HOOK_REGISTERED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    session::on_end(|| {
+        END_HOOK_REGISTERED.store(false, Ordering::SeqCst);
+        let slots: Vec<_> = match REGISTRY.lock() {
+            Ok(mut registry) => registry
+                .take()
+                .map(|registry| registry.into_values().collect())
+                .unwrap_or_default(),
+            Err(_) => return,
+        };
+        for slot in slots {
+            if let Ok(mut slot) = slot.try_lock() {
+                slot.retained.take();
+            }
+        }
+    });
+}
+
+fn is_registered(key: &str, slot: &Arc<tokio::sync::Mutex<Slot>>) -> bool {
+    match REGISTRY.lock() {
+        Ok(registry) => registry
+            .as_ref()
+            .and_then(|registry| registry.get(key))
+            .map(|registered| Arc::ptr_eq(registered, slot))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+// A slot that is locked is being acquired, and is left for that acquisition to use
+fn prune(key: &str) {
+    let mut registry = match REGISTRY.lock() {
+        Ok(registry) => registry,
+        Err(_) => return,
+    };
+    let registry = match registry.as_mut() {
+        Some(registry) => registry,
+        None => return,
+    };
+    let unused = match registry.get(key).map(|slot| slot.try_lock()) {
+        Some(Ok(mut slot)) => {
+            slot.weak = None;
+            true
+        }
+        _ => false,
+    };
+    if unused {
+        debug!("Releasing shared container slot {key}");
+        registry.remove(key);
+    }
+}
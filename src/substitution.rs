@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use log::{debug, error};
+
+use crate::{ImageReference, TestcontainerError};
+
+pub const TESTCONTAINERS_HUB_IMAGE_NAME_PREFIX: &str = "TESTCONTAINERS_HUB_IMAGE_NAME_PREFIX";
+pub const TESTCONTAINERS_IMAGE_SUBSTITUTION_FILE: &str = "TESTCONTAINERS_IMAGE_SUBSTITUTION_FILE";
+
+static CONFIGURED: RwLock<Option<Arc<dyn ImageNameSubstitutor>>> = RwLock::new(None);
+static FROM_ENVIRONMENT: OnceLock<Arc<dyn ImageNameSubstitutor>> = OnceLock::new();
+
+pub trait ImageNameSubstitutor: Send + Sync + Debug {
+    fn substitute(&self, fullname: &str) -> String;
+}
+
+pub fn set_image_name_substitutor<S: ImageNameSubstitutor + 'static>(substitutor: S) {
+    *CONFIGURED.write().unwrap() = Some(Arc::new(substitutor));
+}
+
+pub fn image_name_substitutor() -> Arc<dyn ImageNameSubstitutor> {
+    if let Some(substitutor) = CONFIGURED.read().unwrap().as_ref() {
+        return substitutor.clone();
+    }
+    FROM_ENVIRONMENT
+        .get_or_init(|| Arc::new(from_environment()))
+        .clone()
+}
+
+pub(crate) fn substitute(fullname: &str) -> String {
+    let substituted = image_name_substitutor().substitute(fullname);
+    if substituted != fullname {
+        debug!("Substituted image {} with {}", fullname, substituted);
+    }
+    substituted
+}
+
+fn from_environment() -> ChainedSubstitutor {
+    let mut chain = ChainedSubstitutor::new();
+
+    if let Ok(path) = std::env::var(TESTCONTAINERS_IMAGE_SUBSTITUTION_FILE) {
+        match MappingSubstitutor::from_file(&path) {
+            Ok(mapping) => chain = chain.with(mapping),
+            Err(err) => error!(
+                "Ignoring {} '{}': {err}",
+                TESTCONTAINERS_IMAGE_SUBSTITUTION_FILE, path
+            ),
+        }
+    }
+
+    if let Some(prefix) = HubImageNamePrefixSubstitutor::from_env() {
+        chain = chain.with(prefix);
+    }
+
+    chain
+}
+
+#[derive(Clone, Debug)]
+pub struct HubImageNamePrefixSubstitutor {
+    prefix: String,
+}
+
+impl HubImageNamePrefixSubstitutor {
+    pub fn new<P: Into<String>>(prefix: P) -> HubImageNamePrefixSubstitutor {
+        HubImageNamePrefixSubstitutor {
+            prefix: prefix.into(),
+        }
+    }
+
+    pub fn from_env() -> Option<HubImageNamePrefixSubstitutor> {
+        std::env::var(TESTCONTAINERS_HUB_IMAGE_NAME_PREFIX)
+            .ok()
+            .filter(|prefix| !prefix.is_empty())
+            .map(HubImageNamePrefixSubstitutor::new)
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl ImageNameSubstitutor for HubImageNamePrefixSubstitutor {
+    fn substitute(&self, fullname: &str) -> String {
+        match ImageReference::parse(fullname) {
+            Ok(reference) if reference.is_docker_hub() => format!(
+                "{}/{}",
+                self.prefix.trim_end_matches('/'),
+                reference.without_domain()
+            ),
+            _ => fullname.to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MappingSubstitutor {
+    mappings: HashMap<String, String>,
+}
+
+impl MappingSubstitutor {
+    pub fn new() -> MappingSubstitutor {
+        Default::default()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<MappingSubstitutor, TestcontainerError> {
+        let contents = std::fs::read_to_string(path.as_ref())?;
+        let mut substitutor = MappingSubstitutor::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                    substitutor = substitutor.with_mapping(from.trim(), to.trim());
+                }
+                _ => {
+                    return Err(TestcontainerError::Generic {
+                        message: format!(
+                            "Invalid image mapping on line {} of {}: '{line}'",
+                            number + 1,
+                            path.as_ref().display()
+                        ),
+                    })
+                }
+            }
+        }
+        Ok(substitutor)
+    }
+
+    pub fn with_mapping<F: Into<String>, T: Into<String>>(mut self, from: F, to: T) -> Self {
+        self.mappings.insert(from.into(), to.into());
+        self
+    }
+
+    pub fn mappings(&self) -> &HashMap<String, String> {
+        &self.mappings
+    }
+}
+
+impl ImageNameSubstitutor for MappingSubstitutor {
+    fn substitute(&self, fullname: &str) -> String {
+        if let Some(to) = self.mappings.get(fullname) {
+            return to.clone();
+        }
+
+        // A mapping for the bare repository keeps the tag or digest of the requested image
+        if let Ok(reference) = ImageReference::parse(fullname) {
+            if let Some(to) = self.mappings.get(reference.name()) {
+                return format!("{to}{}", reference.qualifier().suffix());
+            }
+        }
+
+        fullname.to_owned()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ChainedSubstitutor {
+    substitutors: Vec<Box<dyn ImageNameSubstitutor>>,
+}
+
+impl ChainedSubstitutor {
+    pub fn new() -> ChainedSubstitutor {
+        Default::default()
+    }
+
+    pub fn with<S: ImageNameSubstitutor + 'static>(mut self, substitutor: S) -> Self {
+        self.substitutors.push(Box::new(substitutor));
+        self
+    }
+}
+
+impl ImageNameSubstitutor for ChainedSubstitutor {
+    fn substitute(&self, fullname: &str) -> String {
+        self.substitutors
+            .iter()
+            .fold(fullname.to_owned(), |name, substitutor| {
+                substitutor.substitute(&name)
+            })
+    }
+}
@@ -1,9 +1,7 @@
 use futures::StreamExt;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::async_trait;
-use crate::bollard::container::LogsOptions;
-use crate::bollard::exec::{CreateExecOptions, StartExecResults};
 use crate::task::Task;
 use crate::{ContainerHandle, TestcontainerError};
 
@@ -35,14 +33,7 @@ impl Task for MatchLogOutput {
     type Return = ();
 
     async fn execute(&self, handle: &ContainerHandle) -> Result<Self::Return, TestcontainerError> {
-        let log_options = Some(LogsOptions {
-            follow: true,
-            stdout: true,
-            stderr: true,
-            ..Default::default()
-        });
-
-        let mut logstream = handle.docker().logs::<String>(handle.id(), log_options);
+        let mut logstream = handle.runtime().logs(handle.id(), true, None);
 
         let mut remaining_patterns = self.patterns.iter();
         let mut current_pattern = remaining_patterns.next();
@@ -50,7 +41,7 @@ impl Task for MatchLogOutput {
         while let Some(output) = logstream.next().await {
             if let Ok(line) = output {
                 if let Some(pattern) = current_pattern {
-                    if line.to_string().contains(pattern) {
+                    if line.contains(pattern) {
                         current_pattern = remaining_patterns.next();
                         if current_pattern.is_none() {
                             break;
@@ -64,14 +55,19 @@ impl Task for MatchLogOutput {
             }
         }
 
-        Ok(())
+        match current_pattern {
+            None => Ok(()),
+            Some(pattern) => Err(TestcontainerError::Generic {
+                message: format!("Log output ended before '{pattern}' was printed"),
+            }),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Execute {
     cmd: Vec<String>,
-    env: HashMap<String, Option<String>>,
+    env: BTreeMap<String, Option<String>>,
     required_status: Option<u64>,
 }
 
@@ -121,26 +117,21 @@ impl Task for Execute {
             .collect();
 
         let exec = handle
-            .docker()
-            .create_exec(
-                handle.id(),
-                CreateExecOptions {
-                    attach_stdout: Some(true),
-                    attach_stderr: Some(true),
-                    cmd: Some(self.cmd.clone()),
-                    env: Some(env),
-                    ..Default::default()
-                },
-            )
-            .await?
-            .id;
-        if let StartExecResults::Attached { mut output, .. } =
-            handle.docker().start_exec(&exec, None).await?
-        {
-            while let Some(Ok(msg)) = output.next().await {
-                print!("{msg}");
+            .runtime()
+            .exec(handle.id(), self.cmd.clone(), env)
+            .await?;
+
+        let exit_code = exec.exit_code;
+        match self.required_status {
+            Some(required) if exit_code != Some(required as i64) => {
+                Err(TestcontainerError::ExecFailed {
+                    id: handle.id()[..12].to_owned(),
+                    command: self.cmd.clone(),
+                    exit_code,
+                    output: exec.output,
+                })
             }
+            _ => Ok(()),
         }
-        Ok(())
     }
 }
@@ -0,0 +1,320 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Field, Fields, FnArg, ItemFn,
+    LitStr, Type,
+};
+
+// Generates the `Image` accessors for the field of type `ImageSettings`, e.g.
+// `#[derive(Image)] #[image(container = RedisContainer)] struct RedisImage { settings: ImageSettings }`
+#[proc_macro_derive(Image, attributes(image))]
+pub fn derive_image(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_image(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// Generates `Container` for a struct with `ContainerHandle` and `ContainerSettings` fields, and
+// `ServiceContainer`/`AdminContainer` for `#[service_port("5432/tcp")]`/`#[admin_port(..)]`.
+// Any other fields start out as their defaults when a container is attached.
+#[proc_macro_derive(Container, attributes(service_port, admin_port))]
+pub fn derive_container(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_container(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// Runs an async test with its container parameters started concurrently beforehand, e.g.
+// `#[testcontainers_async::test] async fn test_db(pg: PostgresContainer)`. Parameters start from
+// their image's default unless given `#[image(factory)]` or `#[image(expression)]`.
+// `#[testcontainers_async::test(runtime = factory)]` starts them on another runtime. Placed above
+// another test attribute, such as `#[tokio::test]`, the test runs on that attribute's executor.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut runtime = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("runtime") {
+            runtime = Some(meta.value()?.parse::<Expr>()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `runtime = ...`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let function = parse_macro_input!(item as ItemFn);
+    expand_test(function, runtime)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_image(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let settings = field_of_type(input, "ImageSettings")?;
+
+    let mut container = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("image"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("container") {
+                container = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `container = ...`"))
+            }
+        })?;
+    }
+    let container = container.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "#[derive(Image)] needs the container type, e.g. #[image(container = MyContainer)]",
+        )
+    })?;
+
+    let mut expanded = quote! {
+        impl #impl_generics ::testcontainers_async::Image for #name #ty_generics #where_clause {
+            type ContainerType = #container;
+
+            fn settings(&self) -> &::testcontainers_async::ImageSettings {
+                &self.#settings
+            }
+
+            fn settings_mut(&mut self) -> &mut ::testcontainers_async::ImageSettings {
+                &mut self.#settings
+            }
+        }
+    };
+    // A generic image cannot be named from its container type
+    if input.generics.params.is_empty() {
+        expanded.extend(quote! {
+            impl ::testcontainers_async::ContainerImage for #container {
+                type Image = #name;
+            }
+        });
+    }
+    Ok(expanded)
+}
+
+fn expand_container(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let handle = field_of_type(input, "ContainerHandle")?;
+    let settings = field_of_type(input, "ContainerSettings")?;
+    let others = named_fields(input)?
+        .filter(|field| field.ident.as_ref() != Some(&handle))
+        .filter(|field| field.ident.as_ref() != Some(&settings))
+        .map(|field| field.ident.as_ref());
+
+    let mut expanded = quote! {
+        impl #impl_generics ::testcontainers_async::Container for #name #ty_generics #where_clause {
+            fn attach(
+                handle: ::testcontainers_async::ContainerHandle,
+                settings: ::testcontainers_async::ContainerSettings,
+            ) -> Self {
+                #name {
+                    #handle: handle,
+                    #settings: settings,
+                    #(#others: ::core::default::Default::default(),)*
+                }
+            }
+
+            fn handle(&self) -> &::testcontainers_async::ContainerHandle {
+                &self.#handle
+            }
+
+            fn handle_mut(&mut self) -> &mut ::testcontainers_async::ContainerHandle {
+                &mut self.#handle
+            }
+
+            fn settings(&self) -> &::testcontainers_async::ContainerSettings {
+                &self.#settings
+            }
+        }
+    };
+
+    if let Some(port) = port_attribute(input, "service_port")? {
+        expanded.extend(quote! {
+            #[::testcontainers_async::async_trait]
+            impl #impl_generics ::testcontainers_async::ServiceContainer for #name #ty_generics #where_clause {
+                fn internal_service_port(&self) -> &str {
+                    #port
+                }
+            }
+        });
+    }
+    if let Some(port) = port_attribute(input, "admin_port")? {
+        expanded.extend(quote! {
+            #[::testcontainers_async::async_trait]
+            impl #impl_generics ::testcontainers_async::AdminContainer for #name #ty_generics #where_clause {
+                fn internal_admin_port(&self) -> &str {
+                    #port
+                }
+            }
+        });
+    }
+    Ok(expanded)
+}
+
+fn expand_test(mut function: ItemFn, runtime: Option<Expr>) -> Result<TokenStream2, Error> {
+    if function.sig.asyncness.is_none() {
+        return Err(Error::new_spanned(
+            function.sig.fn_token,
+            "the test function must be async",
+        ));
+    }
+
+    let mut images = Vec::new();
+    let mut containers = Vec::new();
+    for (index, input) in function.sig.inputs.iter_mut().enumerate() {
+        let argument = match input {
+            FnArg::Typed(argument) => argument,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new_spanned(receiver, "tests cannot take self"))
+            }
+        };
+
+        let mut image = None;
+        let mut attrs = Vec::new();
+        for attr in argument.attrs.drain(..) {
+            if attr.path().is_ident("image") {
+                image = Some(call_factory(attr.parse_args()?));
+            } else {
+                attrs.push(attr);
+            }
+        }
+        argument.attrs = attrs;
+
+        let ty = &argument.ty;
+        images.push(image.unwrap_or_else(|| {
+            quote! {
+                <<#ty as ::testcontainers_async::ContainerImage>::Image
+                    as ::core::default::Default>::default()
+            }
+        }));
+        containers.push(format_ident!("__container{}", index));
+    }
+
+    let attrs = &function.attrs;
+    let vis = &function.vis;
+    let name = &function.sig.ident;
+    let output = &function.sig.output;
+    let inputs = &function.sig.inputs;
+    let body = &function.block;
+    let runtime = match runtime {
+        Some(runtime) => {
+            let runtime = call_factory(runtime);
+            quote! {
+                ::core::option::Option::Some(::std::sync::Arc::new(#runtime)
+                    as ::std::sync::Arc<dyn ::testcontainers_async::ContainerRuntime>)
+            }
+        }
+        None => quote!(::core::option::Option::None),
+    };
+
+    let run = if containers.is_empty() {
+        quote!(body())
+    } else {
+        quote! {
+            ::testcontainers_async::__private::run_test(
+                (#(#images,)*),
+                #runtime,
+                |(#(#containers,)*)| body(#(#containers),*),
+            )
+        }
+    };
+
+    // Another test attribute brings its own executor, which then drives the test body
+    if attrs.iter().any(is_test_attribute) {
+        return Ok(quote! {
+            #(#attrs)*
+            #vis async fn #name() #output {
+                async fn body(#inputs) #output #body
+                #run.await
+            }
+        });
+    }
+
+    Ok(quote! {
+        #(#attrs)*
+        #[::core::prelude::v1::test]
+        #vis fn #name() #output {
+            async fn body(#inputs) #output #body
+            ::testcontainers_async::__private::block_on(#run)
+        }
+    })
+}
+
+// `#[test]`, `#[tokio::test]`, `#[async_std::test]` and the like
+fn is_test_attribute(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "test")
+}
+
+// A bare path names a factory function, anything else is used as the value itself
+fn call_factory(expr: Expr) -> TokenStream2 {
+    match expr {
+        Expr::Path(path) => quote!(#path()),
+        expr => quote!(#expr),
+    }
+}
+
+fn named_fields(input: &DeriveInput) -> Result<impl Iterator<Item = &Field>, Error> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.named.iter()),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                "only structs with named fields can be derived",
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            "only structs with named fields can be derived",
+        )),
+    }
+}
+
+// Fields are found by the last segment of their type, so `crate::ImageSettings` works as well
+fn field_of_type(input: &DeriveInput, type_name: &str) -> Result<Ident, Error> {
+    let mut matching = named_fields(input)?.filter(|field| match &field.ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == type_name),
+        _ => false,
+    });
+
+    match (matching.next(), matching.next()) {
+        (Some(field), None) => Ok(field.ident.clone().expect("Named field")),
+        (None, _) => Err(Error::new_spanned(
+            &input.ident,
+            format!("expected a field of type {type_name}"),
+        )),
+        (Some(_), Some(field)) => Err(Error::new_spanned(
+            field,
+            format!("expected a single field of type {type_name}"),
+        )),
+    }
+}
+
+fn port_attribute(input: &DeriveInput, name: &str) -> Result<Option<LitStr>, Error> {
+    let mut attrs = input.attrs.iter().filter(|attr| attr.path().is_ident(name));
+    match (attrs.next(), attrs.next()) {
+        (None, _) => Ok(None),
+        (Some(attr), None) => attr.parse_args::<LitStr>().map(Some),
+        (Some(_), Some(attr)) => Err(Error::new_spanned(
+            attr,
+            format!("#[{name}] can only be given once"),
+        )),
+    }
+}
@@ -1,6 +1,8 @@
-use testcontainers_async::{async_trait, ContainerSettings, TestcontainerError};
 use testcontainers_async::{Container, ContainerHandle, Image, ImageSettings};
+use testcontainers_async::{ContainerSettings, TestcontainerError};
 
+#[derive(Image)]
+#[image(container = ExampleContainer)]
 pub struct ExampleImage {
     settings: ImageSettings,
 }
@@ -13,20 +15,9 @@ impl Default for ExampleImage {
     }
 }
 
-#[async_trait]
-impl Image for ExampleImage {
-    type ContainerType = ExampleContainer;
-
-    fn settings(&self) -> &ImageSettings {
-        &self.settings
-    }
-
-    fn settings_mut(&mut self) -> &mut ImageSettings {
-        &mut self.settings
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Container)]
+#[service_port("6379/tcp")]
+#[admin_port("8001/tcp")]
 pub struct ExampleContainer {
     handle: ContainerHandle,
     settings: ContainerSettings,
@@ -37,22 +28,3 @@ impl ExampleContainer {
         self.host_port_for("6379").await
     }
 }
-
-#[async_trait]
-impl Container for ExampleContainer {
-    fn attach(handle: ContainerHandle, settings: ContainerSettings) -> Self {
-        ExampleContainer { handle, settings }
-    }
-
-    fn handle(&self) -> &ContainerHandle {
-        &self.handle
-    }
-
-    fn handle_mut(&mut self) -> &mut ContainerHandle {
-        &mut self.handle
-    }
-
-    fn settings(&self) -> &ContainerSettings {
-        &self.settings
-    }
-}